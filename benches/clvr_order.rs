@@ -0,0 +1,63 @@
+// Times clvr_order scoring candidates on a single thread and on the default rayon pool.
+// cargo bench --bench clvr_order
+// NOTE: the crate has no library target, the modules the ordering depends on are built into the bench from src.
+// Most of them go unused here, as do the imports of their test modules when the bench is checked with cfg(test)
+#![allow(dead_code, unused_imports)]
+
+use std::time::Instant;
+
+use alloy::primitives::U256;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[path = "../src/clvr/mod.rs"]
+mod clvr;
+#[path = "../src/trades/mod.rs"]
+mod trades;
+
+use clvr::algorithm::{clvr_order, Weights};
+use clvr::model::clvr_model::CLVRModel;
+use clvr::model::Omega;
+use trades::implementation::Trade;
+use trades::TradeDirection;
+
+// a batch of random trades between 0.01 and 1 tokens
+fn random_batch(n: usize, seed: u64) -> Omega {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut omega: Omega = Omega::new();
+    for _ in 0..n {
+        let amount = U256::from(rng.gen_range(10_000_000_000_000_000u128..1_000_000_000_000_000_000u128));
+        let direction = if rng.gen() { TradeDirection::Buy } else { TradeDirection::Sell };
+        omega.push(Box::new(Trade::new(amount, direction)));
+    }
+    omega
+}
+
+fn main() {
+    // against a pool of 1000 tokens each
+    let model = CLVRModel::new(U256::from(1000u128 * 10u128.pow(18)), U256::from(1000u128 * 10u128.pow(18)));
+    let p_0 = U256::from(10u128.pow(18));
+    let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+
+    for n in [1000, 2000] {
+        let mut sequential = random_batch(n, 7);
+        let start = Instant::now();
+        single_thread.install(|| clvr_order(&model, p_0, &mut sequential, None, &Weights::default()));
+        let sequential_time = start.elapsed().as_secs_f64();
+
+        let mut parallel = random_batch(n, 7);
+        let start = Instant::now();
+        clvr_order(&model, p_0, &mut parallel, None, &Weights::default());
+        let parallel_time = start.elapsed().as_secs_f64();
+
+        assert!(sequential == parallel);
+        println!(
+            "{} trades: sequential {:.2}s, parallel {:.2}s on {} threads, speedup {:.2}x",
+            n,
+            sequential_time,
+            parallel_time,
+            rayon::current_num_threads(),
+            sequential_time / parallel_time
+        );
+    }
+}
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+// Executes the ordered batches of the service on V2 pairs, V3 pools and Curve pools (see src/server/clvr_router.rs).
+// Every trader approves this contract for the token it sells, only the executor may submit batches. Each call pulls
+// what its trader may pay, lets the target (a router, or a Curve pool swapping itself) spend it for the swap, which
+// delivers to the trader's recipient, and refunds the trader whatever the swap did not spend, measured on the balance
+// of this contract. A call whose pull or swap fails leaves its trader untouched and the batch goes on, the outcome of
+// every call being emitted
+contract ClvrRouter {
+    struct Call {
+        address from;
+        address tokenIn;
+        uint256 amountIn;
+        address target;
+        bytes data;
+    }
+
+    event Executed(uint256 indexed index, bool success, uint256 spent);
+
+    error NotExecutor();
+    error RefundFailed(uint256 index);
+
+    address public immutable executor;
+
+    constructor(address _executor) {
+        executor = _executor;
+    }
+
+    modifier onlyExecutor() {
+        if (msg.sender != executor) revert NotExecutor();
+        _;
+    }
+
+    // executes the calls in order, each on its own
+    function execute(Call[] calldata calls) external onlyExecutor {
+        for (uint256 i = 0; i < calls.length; i++) {
+            Call calldata call = calls[i];
+            uint256 balance = _balance(call.tokenIn);
+
+            if (!_token(call.tokenIn, abi.encodeCall(IERC20.transferFrom, (call.from, address(this), call.amountIn)))) {
+                emit Executed(i, false, 0);
+                continue;
+            }
+
+            bool success = _token(call.tokenIn, abi.encodeCall(IERC20.approve, (call.target, call.amountIn)));
+            if (success) {
+                (success,) = call.target.call(call.data);
+            }
+            // no allowance is left to the target, some tokens only approve from a zero allowance
+            _token(call.tokenIn, abi.encodeCall(IERC20.approve, (call.target, 0)));
+
+            // what the pull brought in and the swap did not spend, all of it if the swap failed
+            uint256 unspent = _balance(call.tokenIn) - balance;
+            if (unspent > 0 && !_token(call.tokenIn, abi.encodeCall(IERC20.transfer, (call.from, unspent)))) {
+                revert RefundFailed(i);
+            }
+
+            emit Executed(i, success, call.amountIn - unspent);
+        }
+    }
+
+    function _balance(address token) internal view returns (uint256) {
+        return IERC20(token).balanceOf(address(this));
+    }
+
+    // calls token, tolerating tokens which return nothing instead of true
+    function _token(address token, bytes memory data) internal returns (bool) {
+        (bool success, bytes memory result) = token.call(data);
+        return success && (result.length == 0 || abi.decode(result, (bool)));
+    }
+}
+
+interface IERC20 {
+    function balanceOf(address account) external view returns (uint256);
+    function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    function approve(address spender, uint256 amount) external returns (bool);
+    function transfer(address to, uint256 amount) external returns (bool);
+}
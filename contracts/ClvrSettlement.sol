@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.24;
+
+import {IPoolManager} from "v4-core/src/interfaces/IPoolManager.sol";
+import {IUnlockCallback} from "v4-core/src/interfaces/callback/IUnlockCallback.sol";
+import {IERC20Minimal} from "v4-core/src/interfaces/external/IERC20Minimal.sol";
+import {BalanceDelta} from "v4-core/src/types/BalanceDelta.sol";
+import {Currency} from "v4-core/src/types/Currency.sol";
+import {PoolKey} from "v4-core/src/types/PoolKey.sol";
+import {TransientStateLibrary} from "v4-core/src/libraries/TransientStateLibrary.sol";
+
+// Executes the ordered batches of the service on V4 pools inside a single unlock of the PoolManager (see
+// src/server/v4_settlement.rs). Every trader approves this contract for the token it sells, only the executor may
+// submit batches. A batch is either executed swap by swap (execute) or, when its trades are matched against each other
+// or cleared at a uniform price, settled at once (settle).
+// NOTE: native ETH can be received but not sold, as it cannot be pulled from the trader
+contract ClvrSettlement is IUnlockCallback {
+    using TransientStateLibrary for IPoolManager;
+
+    struct Swap {
+        PoolKey key;
+        address from;
+        address recipient;
+        IPoolManager.SwapParams params;
+        uint256 amountOutMinimum;
+        uint256 amountInMaximum;
+    }
+
+    struct Payout {
+        address from;
+        address recipient;
+        bool zeroForOne;
+        uint256 amountIn;
+        uint256 amountOut;
+        uint256 amountOutMinimum;
+        uint256 amountInMaximum;
+    }
+
+    error NotExecutor();
+    error NotPoolManager();
+    error NativeInput(uint256 index);
+    error TooLittleReceived(uint256 index, uint256 amountOut);
+    error TooMuchRequested(uint256 index, uint256 amountIn);
+    error Insolvent(Currency currency);
+    error Unclaimed(Currency currency);
+
+    IPoolManager public immutable poolManager;
+    address public immutable executor;
+
+    constructor(IPoolManager _poolManager, address _executor) {
+        poolManager = _poolManager;
+        executor = _executor;
+    }
+
+    modifier onlyExecutor() {
+        if (msg.sender != executor) revert NotExecutor();
+        _;
+    }
+
+    // executes the swaps in order, reverting the whole batch if any swap is outside its bounds
+    function execute(Swap[] calldata swaps) external onlyExecutor {
+        poolManager.unlock(abi.encode(false, abi.encode(swaps)));
+    }
+
+    // executes swaps in the pool of key, then pays every payout, reverting the whole batch if any payout is outside its
+    // bounds or the swaps do not cover the payouts. What is left over is returned to the payouts (see _refund)
+    function settle(PoolKey calldata key, IPoolManager.SwapParams[] calldata swaps, Payout[] calldata payouts)
+        external
+        onlyExecutor
+    {
+        poolManager.unlock(abi.encode(true, abi.encode(key, swaps, payouts)));
+    }
+
+    function unlockCallback(bytes calldata data) external returns (bytes memory) {
+        if (msg.sender != address(poolManager)) revert NotPoolManager();
+        (bool settled, bytes memory batch) = abi.decode(data, (bool, bytes));
+
+        if (settled) {
+            (PoolKey memory key, IPoolManager.SwapParams[] memory swaps, Payout[] memory payouts) =
+                abi.decode(batch, (PoolKey, IPoolManager.SwapParams[], Payout[]));
+            _settle(key, swaps, payouts);
+        } else {
+            _execute(abi.decode(batch, (Swap[])));
+        }
+
+        return "";
+    }
+
+    function _execute(Swap[] memory swaps) internal {
+        for (uint256 i = 0; i < swaps.length; i++) {
+            Swap memory swap = swaps[i];
+            BalanceDelta delta = poolManager.swap(swap.key, swap.params, "");
+
+            // the swap owes the pool a negative delta of the currency in and is owed a positive one of the currency out
+            (Currency currencyIn, Currency currencyOut, int128 deltaIn, int128 deltaOut) = swap.params.zeroForOne
+                ? (swap.key.currency0, swap.key.currency1, delta.amount0(), delta.amount1())
+                : (swap.key.currency1, swap.key.currency0, delta.amount1(), delta.amount0());
+            uint256 amountIn = uint256(uint128(-deltaIn));
+            uint256 amountOut = uint256(uint128(deltaOut));
+
+            if (amountOut < swap.amountOutMinimum) revert TooLittleReceived(i, amountOut);
+            if (amountIn > swap.amountInMaximum) revert TooMuchRequested(i, amountIn);
+            if (currencyIn.isAddressZero()) revert NativeInput(i);
+
+            _pay(currencyIn, swap.from, amountIn);
+            poolManager.take(currencyOut, swap.recipient, amountOut);
+        }
+    }
+
+    function _settle(PoolKey memory key, IPoolManager.SwapParams[] memory swaps, Payout[] memory payouts) internal {
+        // the pool's swaps leave this contract owing their input and owed their output
+        for (uint256 i = 0; i < swaps.length; i++) {
+            poolManager.swap(key, swaps[i], "");
+        }
+
+        for (uint256 i = 0; i < payouts.length; i++) {
+            Payout memory payout = payouts[i];
+            if (payout.amountOut < payout.amountOutMinimum) revert TooLittleReceived(i, payout.amountOut);
+            if (payout.amountIn > payout.amountInMaximum) revert TooMuchRequested(i, payout.amountIn);
+
+            (Currency currencyIn, Currency currencyOut) =
+                payout.zeroForOne ? (key.currency0, key.currency1) : (key.currency1, key.currency0);
+            if (currencyIn.isAddressZero()) revert NativeInput(i);
+
+            _pay(currencyIn, payout.from, payout.amountIn);
+            poolManager.take(currencyOut, payout.recipient, payout.amountOut);
+        }
+
+        _refund(key.currency0, true, payouts);
+        _refund(key.currency1, false, payouts);
+    }
+
+    // pays the PoolManager amount of currency from the allowance of from
+    function _pay(Currency currency, address from, uint256 amount) internal {
+        poolManager.sync(currency);
+        IERC20Minimal(Currency.unwrap(currency)).transferFrom(from, address(poolManager), amount);
+        poolManager.settle();
+    }
+
+    // Returns what the batch left over of currency to the payouts which paid it, in proportion to what each paid. If
+    // none paid it, it is what the pool delivered beyond the payouts and goes to the payouts receiving it, in
+    // proportion to what each received. A debt left means the payouts exceed what the batch brought in
+    function _refund(Currency currency, bool isCurrency0, Payout[] memory payouts) internal {
+        int256 delta = poolManager.currencyDelta(address(this), currency);
+        if (delta < 0) revert Insolvent(currency);
+        if (delta == 0) return;
+
+        bool paid = _total(payouts, isCurrency0, true) > 0;
+        uint256 total = _total(payouts, isCurrency0, paid);
+        if (total == 0) revert Unclaimed(currency);
+
+        // each takes its share of what the previous ones left, the last one taking all that is left
+        uint256 left = uint256(delta);
+        for (uint256 i = 0; i < payouts.length; i++) {
+            uint256 share = _share(payouts[i], isCurrency0, paid);
+            if (share == 0) continue;
+
+            uint256 amount = left * share / total;
+            total -= share;
+            left -= amount;
+            if (amount > 0) poolManager.take(currency, paid ? payouts[i].from : payouts[i].recipient, amount);
+        }
+    }
+
+    // what a payout paid of the currency, or if not paid, what it received of it
+    function _share(Payout memory payout, bool isCurrency0, bool paid) internal pure returns (uint256) {
+        if (paid) return payout.zeroForOne == isCurrency0 ? payout.amountIn : 0;
+        return payout.zeroForOne != isCurrency0 ? payout.amountOut : 0;
+    }
+
+    function _total(Payout[] memory payouts, bool isCurrency0, bool paid) internal pure returns (uint256 total) {
+        for (uint256 i = 0; i < payouts.length; i++) {
+            total += _share(payouts[i], isCurrency0, paid);
+        }
+    }
+}
@@ -1,56 +1,225 @@
-use crate::clvr::model::clvr_model::CLVRModel;
-use crate::clvr::model::{Model, Omega};
+use crate::clvr::metrics::{relative_surplus, Quote};
+use crate::clvr::model::{Model, Omega, SingleTrade};
+use crate::clvr::real::{self, Real};
+use crate::trades::ITrade;
 use alloy::primitives::U256;
-use rug::ops::Pow;
-use rug::{Float, Integer};
-
-// const LOG_E_WEI_STR: &str = "42446531673892822312";
-// const WEI: &str = "000000000000000000";
-// const LOG_E_WEI: Lazy<U256> = Lazy::new(|| U256::from_str_radix(LOG_E_WEI_STR, 10).expect("Failed to convert string to U256"));
-// const BASE: Lazy<U256> = Lazy::new(|| U256::from_str_radix("10000000000000000000", 10).unwrap());
-
-// NOTE: actually computes log_{e * 10 ** 18}{x} so that the result is accurate for a wei representation of a number
-// A property used is log_a(x) = log_b(x) / log_b(a)
-fn ln(x: U256) -> Float {
-    let x_int =
-        Integer::from_str_radix(&x.to_string(), 10).expect("Failed to convert U256 to Integer");
-    let x_float: Float = Float::with_val(256, &x_int) * Float::with_val(18, &10).pow(-18);
-    let ln_x = x_float.ln();
-
-    ln_x
-}
-
-impl CLVRModel {
-    pub fn clvr_order(&self, p_0: U256, omega: &mut Omega) {
-        let size = omega.len();
-        let ln_p0 = ln(p_0);
-        let two = Float::with_val(18, &2);
-
-        // think of this as a selection sort algorithm
-        // iterating through 1 to size+1 because omega is 1-indexed
-        for t in 1..size + 1 {
-            // select t'th trade by minimizing ( ln(p_0) - ln(P(o, t)) )^2
-            let mut candidate_index = t;
-            let mut candidate_value = (ln_p0.clone() - ln(self.P(omega, t))).pow(two.clone());
-
-            for i in t + 1..size + 1 {
-                // try each trade at position t
-                omega.swap(t, i); // simulate that trade i is at position t
-
-                let value = (ln_p0.clone() - ln(self.P(omega, t))).pow(two.clone()); // compute the value for this omega
-
-                if value < candidate_value {
-                    candidate_index = i;
-                    candidate_value = value;
-                }
-
-                omega.swap(i, t); // swap back to preserve original state
-            }
+use rayon::prelude::*;
+use serde::Serialize;
+
+// NOTE: ln(x) is the natural log of x / 10 ** 18, x being a price scaled by 10 ** 18 (see Model::P).
+// Prices are per whole token, which keeps ln(p_0) and ln(P) comparable across pairs with different decimals
+pub(super) use crate::clvr::real::ln;
+
+// Weights of the terms of the ordering objective, the default only minimizes volatility
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct Weights {
+    pub volatility: f64, // sum over t of ( ln(p_0) - ln(P(o, t)) )^2
+    pub surplus: f64,    // subtracts the sum over trades of ln(execution price / p_0), signed so that a gain is positive
+    pub slippage: f64,   // max over trades of the relative loss ln(p_0 / execution price)
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            volatility: 1.0,
+            surplus: 0.0,
+            slippage: 0.0,
+        }
+    }
+}
+
+impl Weights {
+    fn prices_trades(&self) -> bool {
+        self.surplus != 0.0 || self.slippage != 0.0
+    }
+}
+
+// Orders omega greedily. With max_displacement = Some(k) every trade stays within k positions of its arrival slot:
+// a trade arriving at slot a may only be selected from position a - k on, and is selected at position a + k at the
+// latest (aging). Returns the arrival slot of the trade at each position.
+// NOTE: the candidates of each position are scored in parallel, the result is the same as scoring them one by one
+pub fn clvr_order<T: ITrade>(
+    model: &dyn Model,
+    p_0: U256,
+    omega: &mut Omega<T>,
+    max_displacement: Option<usize>,
+    weights: &Weights,
+) -> Vec<usize> {
+    let size = omega.len();
+    let ln_p0 = ln(p_0);
+    let mut arrival: Vec<usize> = (1..size + 1).collect(); // mirrors the swaps on omega
+    let mut worst: Option<Real> = None; // worst slippage of the trades selected so far
+
+    // think of this as a selection sort algorithm
+    // iterating through 1 to size+1 because omega is 1-indexed
+    for t in 1..size + 1 {
+        let eligible = |a: usize| max_displacement.is_none_or(|k| a <= t + k);
+        // a trade that arrived k slots ago has waited long enough and must be selected now
+        let forced = max_displacement.and_then(|k| (t..size + 1).find(|&i| arrival[i - 1] + k == t));
+
+        // the first t - 1 trades are fixed, every candidate for position t is executed against the same pool
+        let prefix = model.after(omega, t - 1);
+
+        // select t'th trade by minimizing its weighted cost, by default ( ln(p_0) - ln(P(o, t)) )^2
+        let candidate_index = match forced {
+            Some(i) => i,
+            None => {
+                let candidates: Vec<usize> = (t..size + 1).filter(|&i| eligible(arrival[i - 1])).collect();
+                let omega: &Omega<T> = omega;
+                let costs: Vec<Real> = candidates
+                    .par_iter()
+                    .map(|&i| candidate_cost(prefix.as_ref(), &ln_p0, &omega[i], weights, worst.as_ref()).0)
+                    .collect();
 
-            if t != candidate_index {
-                // if omega exists with a better value, swap to that omega
-                omega.swap(candidate_index, t);
+                candidates[earliest_minimum(&costs)]
             }
+        };
+
+        if t != candidate_index {
+            // if omega exists with a better value, swap to that omega
+            omega.swap(candidate_index, t);
+            arrival.swap(candidate_index - 1, t - 1);
+        }
+
+        if weights.prices_trades() {
+            let (_, surplus) = candidate_cost(prefix.as_ref(), &ln_p0, &omega[t], weights, worst.as_ref());
+            update_worst(surplus, &mut worst);
+        }
+    }
+
+    arrival
+}
+
+// Increase of the weighted objective from executing trade against the pool modelled by prefix, given the worst
+// slippage of the trades before it, together with the trade's surplus ln(execution price / p_0).
+// Reads nothing but its arguments, so that candidates can be scored in parallel
+pub(super) fn candidate_cost(
+    prefix: &dyn Model,
+    ln_p0: &Real,
+    trade: &dyn ITrade,
+    weights: &Weights,
+    worst: Option<&Real>,
+) -> (Real, Option<Real>) {
+    let (amount_in, amount_out, price) = prefix.simulate(trade);
+    let mut cost = real::zero();
+    if weights.volatility != 0.0 {
+        cost += (ln_p0.clone() - ln(price)).square() * weights.volatility;
+    }
+
+    if !weights.prices_trades() {
+        return (cost, None);
+    }
+
+    let quote = Quote::new(trade, amount_in, amount_out);
+    let surplus = relative_surplus(&quote, trade.get_direction(), ln_p0, prefix.decimals());
+    if let Some(surplus) = &surplus {
+        let slippage = -surplus.clone();
+        cost -= surplus.clone() * weights.surplus;
+        match worst {
+            Some(worst) if slippage > *worst => cost += (slippage - worst) * weights.slippage,
+            Some(_) => {}
+            None => cost += slippage * weights.slippage,
         }
     }
+
+    (cost, surplus)
+}
+
+// index of the lowest cost, the earliest wins ties as in a sequential scan
+pub(super) fn earliest_minimum(costs: &[Real]) -> usize {
+    let mut best = 0;
+    for (k, cost) in costs.iter().enumerate() {
+        if *cost < costs[best] {
+            best = k;
+        }
+    }
+    best
+}
+
+// folds the slippage of a trade with the given surplus into the worst slippage so far
+pub(super) fn update_worst(surplus: Option<Real>, worst: &mut Option<Real>) {
+    if let Some(surplus) = surplus {
+        let slippage = -surplus;
+        *worst = Some(match worst.take() {
+            Some(w) => w.max(&slippage),
+            None => slippage,
+        });
+    }
+}
+
+// Objective weighted by weights: weights.volatility * objective, minus weights.surplus times the aggregate surplus,
+// plus weights.slippage times the worst slippage
+pub fn weighted_objective<T: ITrade>(model: &dyn Model, p_0: U256, omega: &Omega<T>, weights: &Weights) -> Real {
+    let ln_p0 = ln(p_0);
+    let mut total = real::zero();
+    let mut worst: Option<Real> = None;
+
+    for t in 1..omega.len() + 1 {
+        let prefix = model.after(omega, t - 1);
+        let (cost, surplus) = candidate_cost(prefix.as_ref(), &ln_p0, &omega[t], weights, worst.as_ref());
+        total += cost;
+        update_worst(surplus, &mut worst);
+    }
+
+    total
+}
+
+// Inserts an arriving trade into an ordering at the position minimizing the weighted objective, the earliest on ties.
+// Returns the position the trade was inserted at.
+// NOTE: the trades before position p are costed once, only the trade and the ones after it are re-costed at each p
+pub fn clvr_insert<T: ITrade>(model: &dyn Model, p_0: U256, omega: &mut Omega<T>, trade: T, weights: &Weights) -> usize {
+    let ln_p0 = ln(p_0);
+
+    // pool, cost and worst slippage once the first t trades are executed, for t from 0 to omega.len()
+    let mut prefixes: Vec<(Box<dyn Model>, Real, Option<Real>)> = vec![(model.after(omega, 0), real::zero(), None)];
+    for t in 1..omega.len() + 1 {
+        let (pool, total, worst) = &prefixes[t - 1];
+        let (cost, surplus) = candidate_cost(pool.as_ref(), &ln_p0, &omega[t], weights, worst.as_ref());
+        let mut worst = worst.clone();
+        update_worst(surplus, &mut worst);
+        let next = (pool.after(&SingleTrade(&omega[t]), 1), total.clone() + cost, worst);
+        prefixes.push(next);
+    }
+
+    let mut best: Option<(usize, Real)> = None;
+    for (p, (pool, total, worst)) in prefixes.iter().enumerate().map(|(t, prefix)| (t + 1, prefix)) {
+        // the trade at position p, then the trades from p on shifted by one
+        let (cost, surplus) = candidate_cost(pool.as_ref(), &ln_p0, &trade, weights, worst.as_ref());
+        let mut value = total.clone() + cost;
+        let mut worst = worst.clone();
+        update_worst(surplus, &mut worst);
+        let mut pool = pool.after(&SingleTrade(&trade), 1);
+        for t in p..omega.len() + 1 {
+            let (cost, surplus) = candidate_cost(pool.as_ref(), &ln_p0, &omega[t], weights, worst.as_ref());
+            value += cost;
+            update_worst(surplus, &mut worst);
+            pool = pool.after(&SingleTrade(&omega[t]), 1);
+        }
+
+        if best.as_ref().is_none_or(|(_, best_value)| value < *best_value) {
+            best = Some((p, value));
+        }
+    }
+
+    let p = best.map_or(1, |(p, _)| p);
+    omega.push(trade);
+    omega.move_to(omega.len(), p);
+    p
+}
+
+// ( ln(p_0) - ln(P(o, t)) )^2
+pub(super) fn deviation<T: ITrade>(model: &dyn Model, ln_p0: &Real, omega: &Omega<T>, t: usize) -> Real {
+    (ln_p0.clone() - ln(model.P(omega, t))).square()
+}
+
+// Total objective of an ordering: sum over t of ( ln(p_0) - ln(P(o, t)) )^2
+pub fn objective<T: ITrade>(model: &dyn Model, p_0: U256, omega: &Omega<T>) -> Real {
+    let ln_p0 = ln(p_0);
+    let mut total = real::zero();
+
+    for t in 1..omega.len() + 1 {
+        total += deviation(model, &ln_p0, omega, t);
+    }
+
+    total
 }
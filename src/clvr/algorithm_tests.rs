@@ -1,7 +1,15 @@
+use crate::clvr::algorithm::{clvr_insert, clvr_order, deviation, ln, objective, update_worst, weighted_objective, Weights};
+use crate::clvr::metrics::{quote, quotes, relative_surplus, BatchMetrics};
 use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::v3_model::V3Model;
+use crate::clvr::model::Model;
+use crate::clvr::real::Real;
 use crate::trades::implementation::Trade;
-use crate::trades::TradeDirection;
-use alloy::primitives::U256;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, U160, U256};
+use uniswap_v3_sdk::prelude::Tick;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 #[cfg(test)]
 mod tests {
@@ -52,8 +60,253 @@ mod tests {
 
         let p_0 = U256::from(size(1));
         for mut test_case in test_cases {
-            model.clvr_order(p_0, &mut test_case);
+            clvr_order(&model, p_0, &mut test_case, None, &Weights::default());
             assert!(test_case == expected);
         }
     }
+
+    #[test]
+    fn test_clvr_max_displacement() {
+        let arrivals = || {
+            Omega::new_from(vec![
+                Box::new(Trade::new(size(10), TradeDirection::Buy)),
+                Box::new(Trade::new(size(5), TradeDirection::Sell)),
+                Box::new(Trade::new(size(2), TradeDirection::Sell)),
+            ])
+        };
+        let model = CLVRModel::new(size(100), size(100));
+        let p_0 = size(1);
+
+        // unconstrained, the buy moves from the first to the last position, within one position it has to
+        // be executed second
+        let mut omega = arrivals();
+        let arrival = clvr_order(&model, p_0, &mut omega, Some(1), &Weights::default());
+        let expected = Omega::new_from(vec![
+            Trade::new(size(5), TradeDirection::Sell),
+            Trade::new(size(10), TradeDirection::Buy),
+            Trade::new(size(2), TradeDirection::Sell),
+        ]);
+        assert!(omega == expected);
+        assert_eq!(arrival, vec![2, 1, 3]);
+
+        // no displacement keeps the arrival order
+        let mut omega = arrivals();
+        clvr_order(&model, p_0, &mut omega, Some(0), &Weights::default());
+        assert!(omega == arrivals());
+    }
+
+    #[test]
+    fn test_clvr_insert() {
+        let model = CLVRModel::new(size(100), size(100));
+        let p_0 = size(1);
+        let mut omega: Omega<Trade> = Omega::new();
+
+        // each arriving trade lands at the position of the current plan minimizing the objective
+        let trades = [(10, TradeDirection::Buy), (5, TradeDirection::Sell), (2, TradeDirection::Sell)];
+        for (amount, direction) in trades {
+            let p = clvr_insert(&model, p_0, &mut omega, Trade::new(size(amount), direction), &Weights::default());
+            let planned = objective(&model, p_0, &omega);
+
+            let trade = omega.remove(p);
+            for other in 1..omega.len() + 2 {
+                omega.insert(other, Trade::new(trade.get_amount_in(), trade.get_direction()));
+                assert!(planned <= objective(&model, p_0, &omega));
+                omega.remove(other);
+            }
+            omega.insert(p, trade);
+        }
+
+        // the plan keeps the price around p_0 by putting the buy between the two sells
+        let expected = Omega::new_from(vec![
+            Trade::new(size(5), TradeDirection::Sell),
+            Trade::new(size(10), TradeDirection::Buy),
+            Trade::new(size(2), TradeDirection::Sell),
+        ]);
+        assert!(omega == expected);
+    }
+
+    #[test]
+    fn test_clvr_weights() {
+        let trades = || {
+            Omega::new_from(vec![
+                Trade::new(size(10), TradeDirection::Buy),
+                Trade::new(size(5), TradeDirection::Sell),
+                Trade::new(size(8), TradeDirection::Buy),
+                Trade::new(size(2), TradeDirection::Sell),
+            ])
+        };
+        let model = CLVRModel::new(size(100), size(100));
+        let p_0 = size(1);
+
+        // the default weights only count volatility
+        let omega = trades();
+        assert_eq!(weighted_objective(&model, p_0, &omega, &Weights::default()), objective(&model, p_0, &omega));
+
+        let mut volatility = trades();
+        clvr_order(&model, p_0, &mut volatility, None, &Weights::default());
+        let slippage_weights = Weights {
+            volatility: 0.0,
+            surplus: 0.0,
+            slippage: 1.0,
+        };
+        let mut slippage = trades();
+        clvr_order(&model, p_0, &mut slippage, None, &slippage_weights);
+
+        // weighting slippage does not make the worst trade worse off
+        let report = |omega: &Omega<Trade>| BatchMetrics::new(&model, p_0, omega, quotes(&model, omega), &slippage_weights);
+        assert!(report(&slippage).worst_slippage <= report(&volatility).worst_slippage);
+        assert_eq!(report(&slippage).objective, report(&slippage).worst_slippage);
+    }
+
+    // a batch of random trades between 0.01 and 1 tokens against a pool of 1000 tokens each
+    fn random_batch(n: usize, seed: u64) -> Omega {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut omega: Omega = Omega::new();
+        for _ in 0..n {
+            let amount = U256::from(rng.gen_range(10_000_000_000_000_000u128..1_000_000_000_000_000_000u128));
+            let direction = if rng.gen() { TradeDirection::Buy } else { TradeDirection::Sell };
+            omega.push(Box::new(Trade::new(amount, direction)));
+        }
+        omega
+    }
+
+    // clvr_order as it was before candidates were scored in parallel: every candidate is swapped into position t and
+    // costed on the whole ordering, then swapped back
+    fn reference_order(model: &dyn Model, p_0: U256, omega: &mut Omega, weights: &Weights) {
+        let ln_p0 = ln(p_0);
+        let mut worst: Option<Real> = None;
+
+        let step_cost = |omega: &Omega, t: usize, worst: Option<&Real>| {
+            let mut cost = deviation(model, &ln_p0, omega, t) * weights.volatility;
+            if let Some(surplus) = relative_surplus(&quote(model, omega, t), omega[t].get_direction(), &ln_p0, model.decimals()) {
+                let slippage = -surplus.clone();
+                cost -= surplus * weights.surplus;
+                match worst {
+                    Some(worst) if slippage > *worst => cost += (slippage - worst) * weights.slippage,
+                    Some(_) => {}
+                    None => cost += slippage * weights.slippage,
+                }
+            }
+            cost
+        };
+
+        for t in 1..omega.len() + 1 {
+            let mut candidate_index = t;
+            let mut candidate_value: Option<Real> = None;
+            for i in t..omega.len() + 1 {
+                omega.swap(t, i);
+                let value = step_cost(omega, t, worst.as_ref());
+                if candidate_value.as_ref().is_none_or(|c| value < *c) {
+                    candidate_index = i;
+                    candidate_value = Some(value);
+                }
+                omega.swap(i, t);
+            }
+            omega.swap(candidate_index, t);
+
+            let surplus = relative_surplus(&quote(model, omega, t), omega[t].get_direction(), &ln_p0, model.decimals());
+            update_worst(surplus, &mut worst);
+        }
+    }
+
+    // full range liquidity plus a second position active only between ticks -600 and 600, which trades of the random
+    // batch push the price across
+    fn crossing_v3_model() -> V3Model {
+        let liquidity = 1_000_000_000_000_000_000u128;
+        let ticks = vec![
+            Tick::new(-887220, liquidity, liquidity as i128),
+            Tick::new(-600, liquidity, liquidity as i128),
+            Tick::new(600, liquidity, -(liquidity as i128)),
+            Tick::new(887220, liquidity, -(liquidity as i128)),
+        ];
+        V3Model::new(U160::from(1) << 96, 2 * liquidity, 0, U24::from(3000), ticks)
+    }
+
+    #[test]
+    fn test_clvr_matches_reference() {
+        let clvr_model = CLVRModel::new(size(1000), size(1000));
+        let v3_model = crossing_v3_model();
+        let models: [&dyn Model; 2] = [&clvr_model, &v3_model];
+        let weights = [
+            Weights::default(),
+            Weights {
+                volatility: 1.0,
+                surplus: 0.5,
+                slippage: 0.5,
+            },
+        ];
+
+        for model in models {
+            for weights in &weights {
+                let mut expected = random_batch(40, 7);
+                reference_order(model, size(1), &mut expected, weights);
+                let mut omega = random_batch(40, 7);
+                clvr_order(model, size(1), &mut omega, None, weights);
+                assert!(omega == expected);
+            }
+        }
+
+        // the batch moves the V3 price out of the narrow position, across the ticks at -600 or 600
+        let mut omega = random_batch(40, 7);
+        clvr_order(&v3_model, size(1), &mut omega, None, &Weights::default());
+        let (lower, upper) = (size(94) / U256::from(100), size(106) / U256::from(100));
+        assert!((1..omega.len() + 1).map(|t| v3_model.P(&omega, t)).any(|p| p < lower || p > upper));
+    }
+
+    // clvr_insert as it was before the prefixes were reused: the trade is tried at every position and the whole
+    // ordering costed, the earliest minimum wins
+    fn reference_insert(model: &dyn Model, p_0: U256, omega: &mut Omega<Trade>, trade: Trade, weights: &Weights) -> usize {
+        let mut best: Option<(usize, Real)> = None;
+        for p in 1..omega.len() + 2 {
+            omega.insert(p, trade.clone());
+            let value = weighted_objective(model, p_0, omega, weights);
+            if best.as_ref().is_none_or(|(_, best_value)| value < *best_value) {
+                best = Some((p, value));
+            }
+            omega.remove(p);
+        }
+
+        let p = best.map_or(1, |(p, _)| p);
+        omega.insert(p, trade);
+        p
+    }
+
+    #[test]
+    fn test_clvr_insert_matches_reference() {
+        let clvr_model = CLVRModel::new(size(1000), size(1000));
+        let v3_model = crossing_v3_model();
+        let models: [&dyn Model; 2] = [&clvr_model, &v3_model];
+        let weights = Weights {
+            volatility: 1.0,
+            surplus: 0.5,
+            slippage: 0.5,
+        };
+
+        for model in models {
+            let batch = random_batch(12, 11);
+            let (mut omega, mut expected) = (Omega::new(), Omega::new());
+            for t in 1..batch.len() + 1 {
+                let trade = Trade::new(batch[t].get_amount_in(), batch[t].get_direction());
+                let p = clvr_insert(model, size(1), &mut omega, trade.clone(), &weights);
+                assert_eq!(p, reference_insert(model, size(1), &mut expected, trade, &weights));
+            }
+            assert!(omega == expected);
+        }
+    }
+
+    // candidates scored in parallel give the ordering scored one by one, timed by cargo bench --bench clvr_order
+    #[test]
+    fn test_clvr_order_parallel_matches_sequential() {
+        let model = CLVRModel::new(size(1000), size(1000));
+        let single_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let four_threads = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+
+        let mut sequential = random_batch(100, 7);
+        single_thread.install(|| clvr_order(&model, size(1), &mut sequential, None, &Weights::default()));
+        let mut parallel = random_batch(100, 7);
+        four_threads.install(|| clvr_order(&model, size(1), &mut parallel, None, &Weights::default()));
+
+        assert!(sequential == parallel);
+        assert_eq!(sequential.ids().collect::<Vec<_>>(), parallel.ids().collect::<Vec<_>>());
+    }
 }
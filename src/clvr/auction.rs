@@ -0,0 +1,118 @@
+use crate::clvr::metrics::Quote;
+use crate::clvr::model::{Model, Omega};
+use crate::trades::implementation::Trade;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, U256};
+
+// Net flow of a uniformly cleared batch: the side in excess at p_0 with its volume e, the volume c of the other side,
+// and the amount r of the excess traded against the pool for out
+struct Clearing {
+    excess: TradeDirection,
+    e: U256,
+    c: U256,
+    r: U256,
+    out: U256,
+    fee: U24,
+}
+
+fn volume<T: ITrade>(omega: &Omega<T>, direction: TradeDirection) -> U256 {
+    (1..omega.len() + 1)
+        .filter(|&t| omega[t].get_direction() == direction)
+        .map(|t| omega[t].get_amount_in())
+        .sum()
+}
+
+fn clear<T: ITrade>(model: &dyn Model, p_0: U256, omega: &Omega<T>) -> Clearing {
+    // p_0 is per whole token, so raw amounts of x are rescaled to raw amounts of y
+    let ten = U256::from(10);
+    let (decimals_x, decimals_y) = model.decimals();
+    let scale_x = ten.pow(U256::from(18 + decimals_x as u64));
+    let scale_y = ten.pow(U256::from(decimals_y));
+
+    let sold_x = volume(omega, TradeDirection::Sell);
+    let bought_y = volume(omega, TradeDirection::Buy);
+
+    // the side in excess at p_0 sends its residual to the pool
+    let excess = if sold_x * p_0 * scale_y / scale_x > bought_y {
+        TradeDirection::Sell
+    } else {
+        TradeDirection::Buy
+    };
+    let (e, c) = match excess {
+        TradeDirection::Sell => (sold_x, bought_y),
+        TradeDirection::Buy => (bought_y, sold_x),
+    };
+    let fee = if !omega.is_empty() { omega[1].get_fee() } else { U24::ZERO }; // every trade of the batch is in the same pool
+    let (r, out) = residual(model, excess.clone(), fee, e, c);
+
+    Clearing { excess, e, c, r, out, fee }
+}
+
+// Uniform clearing of a batch: opposite trades are matched against each other and only the net flow trades against
+// the pool, so that every trade of the batch receives the same price. Returns the payout of every trade in the
+// order of omega.
+// NOTE: exact output trades take part with their maximum amount in, price limits are ignored
+pub fn uniform_clearing<T: ITrade>(model: &dyn Model, p_0: U256, omega: &Omega<T>) -> Vec<Quote> {
+    let Clearing { excess, e, c, r, out, .. } = clear(model, p_0, omega);
+    let sold_x = volume(omega, TradeDirection::Sell);
+    let bought_y = volume(omega, TradeDirection::Buy);
+
+    // the excess side receives c + out for e - r matched and r traded, the other side receives e - r for c
+    let (sellers_y, buyers_x) = match excess {
+        TradeDirection::Sell => (c + out, e - r),
+        TradeDirection::Buy => (e - r, c + out),
+    };
+
+    (1..omega.len() + 1)
+        .map(|t| {
+            let amount_in = omega[t].get_amount_in();
+            let amount_out = match omega[t].get_direction() {
+                TradeDirection::Sell => amount_in * sellers_y / sold_x,
+                TradeDirection::Buy => amount_in * buyers_x / bought_y,
+            };
+
+            Quote {
+                amount_in,
+                amount_out,
+                partial: false,
+            }
+        })
+        .collect()
+}
+
+// the single trade of the net flow against the pool, None if the batch clears internally
+pub fn uniform_residual<T: ITrade>(model: &dyn Model, p_0: U256, omega: &Omega<T>) -> Option<Trade> {
+    let Clearing { excess, r, fee, .. } = clear(model, p_0, omega);
+    (!r.is_zero()).then(|| Trade::new(r, excess).with_fee(fee))
+}
+
+// Amount r of the excess e traded against the pool and its output out(r), such that all trades clear at one price:
+// the excess side pays e for c + out(r) and the other side pays c for e - r, so (c + out(r)) * (e - r) = c * e.
+// f(r) = (c + out(r)) * (e - r) - c * e is zero at r = 0, rises while the pool's marginal price beats c / e and
+// then falls, the largest r with f(r) >= 0 is found by bisection
+fn residual(model: &dyn Model, excess: TradeDirection, fee: U24, e: U256, c: U256) -> (U256, U256) {
+    let out = |r: U256| -> U256 {
+        if r.is_zero() {
+            return U256::ZERO;
+        }
+
+        let (_, amount_out, _) = model.simulate(&Trade::new(r, excess.clone()).with_fee(fee));
+        amount_out
+    };
+    let clears = |r: U256| (c + out(r)) * (e - r) >= c * e;
+
+    let (mut lo, mut hi) = (U256::ZERO, e);
+    if clears(hi) {
+        lo = hi;
+    }
+    while hi - lo > U256::from(1) {
+        let mid = (lo + hi) / U256::from(2);
+        if clears(mid) {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    (lo, out(lo))
+}
@@ -0,0 +1,74 @@
+use crate::clvr::auction::{uniform_clearing, uniform_residual};
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::Omega;
+use crate::trades::implementation::Trade;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::U256;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    fn to_f64(x: U256) -> f64 {
+        x.to_string().parse().unwrap()
+    }
+
+    #[test]
+    fn test_uniform_clearing_balanced() {
+        // at p_0 the two trades match exactly, nothing is sent to the pool
+        let model = CLVRModel::new(size(100), size(100));
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Sell)));
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Buy)));
+
+        let payouts = uniform_clearing(&model, size(1), &omega);
+        assert_eq!(payouts[0].amount_out, size(10));
+        assert_eq!(payouts[1].amount_out, size(10));
+        assert!(uniform_residual(&model, size(1), &omega).is_none());
+    }
+
+    #[test]
+    fn test_uniform_clearing_one_sided() {
+        // both sells are executed against the pool as one trade and share its output
+        let model = CLVRModel::new(size(100), size(100));
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Sell)));
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Sell)));
+
+        let payouts = uniform_clearing(&model, size(1), &omega);
+        let expected = size(20) * size(100) / size(120) / U256::from(2);
+        assert!(payouts[0].amount_out.abs_diff(expected) <= U256::from(1));
+        assert_eq!(payouts[0].amount_out, payouts[1].amount_out);
+
+        // the pool receives the whole flow as a single sell
+        let residual = uniform_residual(&model, size(1), &omega).unwrap();
+        assert_eq!(residual.get_direction(), TradeDirection::Sell);
+        assert_eq!(residual.get_amount_in(), size(20));
+    }
+
+    #[test]
+    fn test_uniform_clearing_same_price() {
+        let model = CLVRModel::new(size(100), size(100));
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(size(20), TradeDirection::Sell)));
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Buy)));
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Sell)));
+
+        let payouts = uniform_clearing(&model, size(1), &omega);
+
+        // y per x is the same for sellers and buyers, and below p_0 since the sells are in excess
+        let sell_price = to_f64(payouts[0].amount_out) / to_f64(payouts[0].amount_in);
+        let other_sell_price = to_f64(payouts[2].amount_out) / to_f64(payouts[2].amount_in);
+        let buy_price = to_f64(payouts[1].amount_in) / to_f64(payouts[1].amount_out);
+        assert!((sell_price - buy_price).abs() / buy_price < 1e-9);
+        assert!((sell_price - other_sell_price).abs() / sell_price < 1e-9);
+        assert!(sell_price < 1.0);
+    }
+}
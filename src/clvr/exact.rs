@@ -0,0 +1,116 @@
+use crate::clvr::algorithm::{candidate_cost, clvr_order, ln, update_worst, weighted_objective, Weights};
+use crate::clvr::model::{Model, Omega, SingleTrade};
+use crate::clvr::real::{self, Real};
+use crate::trades::ITrade;
+use alloy::primitives::U256;
+
+// Largest batch the exact solver is meant for, the search is factorial in the batch size
+pub const EXACT_MAX_TRADES: usize = 12;
+// Largest batch when the surplus is weighted, no prefix is then pruned and every permutation is costed
+pub const EXACT_MAX_TRADES_SURPLUS: usize = 8;
+
+// Largest batch the exact solver orders with weights, larger ones are left to the greedy
+pub fn exact_max_trades(weights: &Weights) -> usize {
+    if weights.surplus == 0.0 {
+        EXACT_MAX_TRADES
+    } else {
+        EXACT_MAX_TRADES_SURPLUS
+    }
+}
+
+// Best complete ordering found so far
+struct Incumbent {
+    value: Real,
+    order: Vec<usize>,
+}
+
+// State of the depth first search, order mirrors the swaps applied to omega
+struct Search<'a> {
+    ln_p0: Real,
+    weights: &'a Weights,
+    arrival: &'a [usize], // arrival slot of the trade at each position of the greedy ordering
+    max_displacement: Option<usize>,
+    order: Vec<usize>,
+    best: Incumbent,
+}
+
+// Finds an ordering minimizing the weighted objective (see weighted_objective). With max_displacement = Some(k) only
+// the orderings keeping every trade within k positions of its arrival slot are searched.
+// Permutations are searched depth first. Volatility and the worst slippage never decrease along a prefix, so unless
+// the surplus is weighted every prefix whose partial value already reaches the incumbent is pruned.
+// The greedy ordering is the first incumbent, so the result is never worse than clvr_order.
+pub fn clvr_order_exact<T: ITrade>(
+    model: &dyn Model,
+    p_0: U256,
+    omega: &mut Omega<T>,
+    max_displacement: Option<usize>,
+    weights: &Weights,
+) {
+    let arrival = clvr_order(model, p_0, omega, max_displacement, weights);
+
+    let order: Vec<usize> = (1..omega.len() + 1).collect();
+    let mut search = Search {
+        ln_p0: ln(p_0),
+        weights,
+        arrival: &arrival,
+        max_displacement,
+        best: Incumbent {
+            value: weighted_objective(model, p_0, omega, weights),
+            order: order.clone(),
+        },
+        order,
+    };
+
+    search.search(model.after(omega, 0).as_ref(), omega, 1, real::zero(), None);
+
+    // omega is back in the greedy ordering here, which is what best.order indexes into
+    omega.reorder(&search.best.order);
+}
+
+impl Search<'_> {
+    // arrival slot of the trade at position i of omega
+    fn arrival(&self, i: usize) -> usize {
+        self.arrival[self.order[i - 1] - 1]
+    }
+
+    // fixes position t to every remaining trade in turn, pool is the pool once the first t - 1 trades are executed
+    fn search<T: ITrade>(&mut self, pool: &dyn Model, omega: &mut Omega<T>, t: usize, partial: Real, worst: Option<Real>) {
+        let size = omega.len();
+        if t > size {
+            if partial < self.best.value {
+                self.best.value = partial;
+                self.best.order = self.order.clone();
+            }
+            return;
+        }
+
+        // a trade that arrived k slots ago must be placed now, and no trade may move more than k slots ahead
+        let forced = self.max_displacement.and_then(|k| (t..size + 1).find(|&i| self.arrival(i) + k == t));
+        let candidates: Vec<usize> = match forced {
+            Some(i) => vec![i],
+            None => (t..size + 1)
+                .filter(|&i| self.max_displacement.is_none_or(|k| self.arrival(i) <= t + k))
+                .collect(),
+        };
+
+        for i in candidates {
+            omega.swap(t, i);
+            self.order.swap(t - 1, i - 1);
+
+            let (cost, surplus) = candidate_cost(pool, &self.ln_p0, &omega[t], self.weights, worst.as_ref());
+            let value = partial.clone() + cost;
+            let mut worst = worst.clone();
+            update_worst(surplus, &mut worst);
+
+            // the worst slippage of a prefix without priced trades may still turn into a gain
+            let bounded = self.weights.surplus == 0.0 && (self.weights.slippage == 0.0 || worst.is_some());
+            if !bounded || value < self.best.value {
+                let next = pool.after(&SingleTrade(&omega[t]), 1);
+                self.search(next.as_ref(), omega, t + 1, value, worst);
+            }
+
+            self.order.swap(i - 1, t - 1);
+            omega.swap(i, t);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use crate::clvr::algorithm::{clvr_order, objective, weighted_objective, Weights};
+use crate::clvr::exact::clvr_order_exact;
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::trades::implementation::Trade;
+use crate::trades::TradeDirection;
+use alloy::primitives::U256;
+
+#[cfg(test)]
+mod tests {
+    use crate::clvr::model::Omega;
+
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let x = x.to_string();
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    fn omega(trades: &[(u128, TradeDirection)]) -> Omega {
+        let mut omega: Omega = Omega::new();
+        for (amount, direction) in trades {
+            omega.push(Box::new(Trade::new(size(*amount), direction.clone())));
+        }
+
+        omega
+    }
+
+    #[test]
+    fn test_clvr_exact() {
+        let test_cases: Vec<Vec<(u128, TradeDirection)>> = vec![
+            vec![
+                (5, TradeDirection::Sell),
+                (10, TradeDirection::Buy),
+                (2, TradeDirection::Sell),
+            ],
+            vec![
+                (20, TradeDirection::Buy),
+                (3, TradeDirection::Sell),
+                (7, TradeDirection::Sell),
+                (1, TradeDirection::Buy),
+                (12, TradeDirection::Sell),
+                (4, TradeDirection::Buy),
+            ],
+            vec![
+                (30, TradeDirection::Sell),
+                (2, TradeDirection::Buy),
+                (2, TradeDirection::Buy),
+                (9, TradeDirection::Buy),
+                (15, TradeDirection::Buy),
+                (1, TradeDirection::Sell),
+                (6, TradeDirection::Sell),
+                (11, TradeDirection::Buy),
+            ],
+        ];
+
+        let model = CLVRModel::new(size(100), size(100));
+        let p_0 = size(1);
+
+        for trades in test_cases {
+            let mut greedy = omega(&trades);
+            clvr_order(&model, p_0, &mut greedy, None, &Weights::default());
+            let greedy_value = objective(&model, p_0, &greedy);
+
+            let mut exact = omega(&trades);
+            clvr_order_exact(&model, p_0, &mut exact, None, &Weights::default());
+            let exact_value = objective(&model, p_0, &exact);
+
+            println!(
+                "{} trades: greedy {:.6}, optimal {:.6}, gap {:.6}",
+                trades.len(),
+                greedy_value.to_f64(),
+                exact_value.to_f64(),
+                (greedy_value.clone() - exact_value.clone()).to_f64()
+            );
+
+            assert!(exact_value <= greedy_value);
+            assert_eq!(exact.len(), trades.len());
+        }
+    }
+
+    // every ordering of positions 1..=n, each as the arrival slot of the trade at each position
+    fn permutations(n: usize) -> Vec<Vec<usize>> {
+        if n == 0 {
+            return vec![Vec::new()];
+        }
+        let mut all = Vec::new();
+        for shorter in permutations(n - 1) {
+            for p in 0..n {
+                let mut order = shorter.clone();
+                order.insert(p, n);
+                all.push(order);
+            }
+        }
+        all
+    }
+
+    #[test]
+    fn test_clvr_exact_weights_and_displacement() {
+        let trades = [
+            (20, TradeDirection::Buy),
+            (3, TradeDirection::Sell),
+            (7, TradeDirection::Sell),
+            (1, TradeDirection::Buy),
+            (12, TradeDirection::Sell),
+            (4, TradeDirection::Buy),
+        ];
+        let model = CLVRModel::new(size(100), size(100));
+        let p_0 = size(1);
+        let weights = [
+            Weights {
+                volatility: 1.0,
+                surplus: 0.0,
+                slippage: 2.0,
+            },
+            Weights {
+                volatility: 1.0,
+                surplus: 0.5,
+                slippage: 0.5,
+            },
+        ];
+
+        for weights in &weights {
+            for max_displacement in [None, Some(1), Some(2)] {
+                // the optimum over every ordering within max_displacement of the arrival order
+                let optimum = permutations(trades.len())
+                    .into_iter()
+                    .filter(|order| {
+                        max_displacement.is_none_or(|k| order.iter().enumerate().all(|(t, &a)| a.abs_diff(t + 1) <= k))
+                    })
+                    .map(|order| {
+                        let mut omega = omega(&trades);
+                        omega.reorder(&order);
+                        weighted_objective(&model, p_0, &omega, weights)
+                    })
+                    .reduce(|a, b| if b < a { b } else { a })
+                    .unwrap();
+
+                let ids: Vec<_> = omega(&trades).ids().collect();
+                let mut exact = omega(&trades);
+                clvr_order_exact(&model, p_0, &mut exact, max_displacement, weights);
+
+                assert!(weighted_objective(&model, p_0, &exact, weights) == optimum);
+                if let Some(k) = max_displacement {
+                    let slots: Vec<usize> = exact.ids().map(|id| ids.iter().position(|&i| i == id).unwrap() + 1).collect();
+                    assert!(slots.iter().enumerate().all(|(t, &a)| a.abs_diff(t + 1) <= k));
+                }
+            }
+        }
+    }
+}
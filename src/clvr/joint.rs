@@ -0,0 +1,198 @@
+use crate::clvr::algorithm::{candidate_cost, earliest_minimum, ln, update_worst, Weights};
+use crate::clvr::metrics::{quote, Quote};
+use crate::clvr::model::multi_pool_model::MultiPoolModel;
+use crate::clvr::model::{Omega, TradeId};
+use crate::clvr::real::{self, Real};
+use crate::clvr::slippage::reverts;
+use crate::trades::implementation::Trade;
+use crate::trades::path::Hop;
+use crate::trades::ITrade;
+use alloy::primitives::U256;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+// Increase of the joint objective from executing trade after the trades modelled by prefix, its weighted cost against
+// its own pool. Also returns the trade's surplus.
+// NOTE: a trade does not move the price of the other pools, which the models do not link (arbitrage between them is
+// not modelled), so their deviations are left out: they are the same whichever trade of another pool is selected
+fn joint_cost(prefix: &MultiPoolModel, ln_p0: &[Real], trade: &dyn ITrade, weights: &Weights, worst: Option<&Real>) -> (Real, Option<Real>) {
+    let k = trade.get_pool();
+    candidate_cost(prefix.pool(k), &ln_p0[k], trade, weights, worst)
+}
+
+// Increase of the joint objective from executing the trade at position i and the following legs of its path, next
+// giving the id of the leg after each leg. Also returns the worst slippage once the legs are executed
+fn path_cost<T: ITrade>(
+    prefix: &MultiPoolModel,
+    ln_p0: &[Real],
+    omega: &Omega<T>,
+    i: usize,
+    next: &HashMap<TradeId, TradeId>,
+    weights: &Weights,
+    worst: Option<&Real>,
+) -> (Real, Option<Real>) {
+    let mut model: Option<MultiPoolModel> = None;
+    let mut worst = worst.cloned();
+    let mut total = real::zero();
+    let mut i = i;
+
+    loop {
+        let current = model.as_ref().unwrap_or(prefix);
+        let (cost, surplus) = joint_cost(current, ln_p0, &omega[i], weights, worst.as_ref());
+        total += cost;
+        update_worst(surplus, &mut worst);
+
+        match next.get(&omega.id(i)).and_then(|&id| omega.position(id)) {
+            Some(leg) => {
+                model = Some(current.after_trade(&omega[i]));
+                i = leg;
+            }
+            None => return (total, worst),
+        }
+    }
+}
+
+// Orders the trades of linked pools jointly. Greedily selects the t'th trade minimizing its weighted cost against its
+// own pool. As a trade leaves the other pools untouched, the joint ordering interleaves the orderings of the pools:
+// what links them is the worst slippage, shared by the trades of every pool, and the paths.
+// p_0[k] is the reference price of pool k, every trade is executed against the pool given by its get_pool.
+// paths lists the ids of the legs of every multi-hop trade in path order (see exact_input_legs): the legs are executed
+// by a single router call, so they stay consecutive and in path order, and a path is selected by the cost of all its
+// legs. Returns the arrival slot of the trade at each position
+pub fn clvr_order_joint<T: ITrade>(
+    model: &MultiPoolModel,
+    p_0: &[U256],
+    omega: &mut Omega<T>,
+    paths: &[Vec<TradeId>],
+    weights: &Weights,
+) -> Vec<usize> {
+    let size = omega.len();
+    let ln_p0: Vec<Real> = p_0.iter().map(|&p| ln(p)).collect();
+    let mut arrival: Vec<usize> = (1..size + 1).collect();
+    let mut worst: Option<Real> = None;
+
+    // legs whose previous leg is in the batch only go right after it
+    let next: HashMap<TradeId, TradeId> = paths
+        .iter()
+        .flat_map(|legs| legs.windows(2))
+        .filter(|pair| omega.position(pair[0]).is_some() && omega.position(pair[1]).is_some())
+        .map(|pair| (pair[0], pair[1]))
+        .collect();
+    let followers: HashSet<TradeId> = next.values().copied().collect();
+
+    for t in 1..size + 1 {
+        let prefix = model.after(omega, t - 1);
+
+        let forced = (t > 1)
+            .then(|| next.get(&omega.id(t - 1)))
+            .flatten()
+            .and_then(|&id| omega.position(id));
+        let candidate_index = match forced {
+            Some(leg) => leg,
+            None => {
+                let omega: &Omega<T> = omega;
+                let candidates: Vec<usize> = (t..size + 1).filter(|&i| !followers.contains(&omega.id(i))).collect();
+                let costs: Vec<Real> = candidates
+                    .par_iter()
+                    .map(|&i| path_cost(&prefix, &ln_p0, omega, i, &next, weights, worst.as_ref()).0)
+                    .collect();
+                candidates[earliest_minimum(&costs)]
+            }
+        };
+
+        if t != candidate_index {
+            omega.swap(candidate_index, t);
+            arrival.swap(candidate_index - 1, t - 1);
+        }
+
+        let (_, surplus) = joint_cost(&prefix, &ln_p0, &omega[t], weights, worst.as_ref());
+        update_worst(surplus, &mut worst);
+    }
+
+    arrival
+}
+
+// Decomposes a multi-hop exact input swap of amount_in into one exact input trade per hop, hops[k] being executed
+// against the pool pools[k] of model. Every leg after the first sells what the previous leg is quoted to deliver at the
+// current state of its pool. Only the last leg carries amount_out_minimum, the router checks it once the whole path is
+// executed
+pub fn exact_input_legs(
+    model: &MultiPoolModel,
+    hops: &[Hop],
+    pools: &[usize],
+    amount_in: U256,
+    amount_out_minimum: U256,
+) -> Vec<Trade> {
+    let mut amount = amount_in;
+    let mut legs = Vec::new();
+
+    for (k, (hop, &pool)) in hops.iter().zip(pools).enumerate() {
+        let mut leg = Trade::new(amount, hop.direction()).with_fee(hop.fee).with_pool(pool);
+        if k == hops.len() - 1 {
+            leg = leg.with_amount_out_minimum(amount_out_minimum);
+        }
+        (_, amount, _) = model.simulate(&leg);
+        legs.push(leg);
+    }
+
+    legs
+}
+
+// Joint objective of an ordering of the trades of linked pools: the weighted objective of every trade against its
+// pool, the worst slippage being taken over the trades of every pool
+pub fn joint_objective<T: ITrade>(model: &MultiPoolModel, p_0: &[U256], omega: &Omega<T>, weights: &Weights) -> Real {
+    let ln_p0: Vec<Real> = p_0.iter().map(|&p| ln(p)).collect();
+    let mut total = real::zero();
+    let mut worst: Option<Real> = None;
+
+    for t in 1..omega.len() + 1 {
+        let prefix = model.after(omega, t - 1);
+        let (cost, surplus) = joint_cost(&prefix, &ln_p0, &omega[t], weights, worst.as_ref());
+        total += cost;
+        update_worst(surplus, &mut worst);
+    }
+
+    total
+}
+
+// Excludes the trades of a joint ordering which would revert, each checked against its pool once the trades kept
+// before it are executed. Unlike enforce_slippage a reverting trade is not moved, the joint ordering is kept. A path is
+// executed by a single router call, so a reverting leg excludes every leg of its path (see clvr_order_joint).
+// Returns the quote of every kept trade in order and the excluded trades with their ids, which are deferred
+pub fn enforce_joint_slippage<T: ITrade>(
+    model: &MultiPoolModel,
+    omega: &mut Omega<T>,
+    paths: &[Vec<TradeId>],
+) -> (Vec<Quote>, Vec<(TradeId, T)>) {
+    let mut quotes = Vec::new();
+    let mut deferred = Vec::new();
+
+    let mut t = 1;
+    while t <= omega.len() {
+        let prefix = model.after(omega, t - 1);
+        let pool = prefix.pool(omega[t].get_pool());
+        let single = Omega::new_from(vec![Trade::from(&omega[t] as &dyn ITrade)]);
+        if reverts(pool, &single, 1) {
+            let id = omega.id(t);
+            let legs = paths.iter().find(|legs| legs.contains(&id)).cloned().unwrap_or_else(|| vec![id]);
+            let mut positions: Vec<usize> = legs.iter().filter_map(|&leg| omega.position(leg)).collect();
+            positions.sort_unstable();
+
+            // the legs kept before t are consecutive, their quotes are dropped with them
+            let mut excluded = Vec::new();
+            for &position in positions.iter().rev() {
+                if position < t {
+                    quotes.remove(position - 1);
+                }
+                excluded.push((omega.id(position), omega.remove(position)));
+            }
+            deferred.extend(excluded.into_iter().rev());
+            t = positions[0];
+        } else {
+            quotes.push(quote(pool, &single, 1));
+            t += 1;
+        }
+    }
+
+    (quotes, deferred)
+}
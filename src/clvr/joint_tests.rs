@@ -0,0 +1,169 @@
+use crate::clvr::algorithm::{clvr_order, Weights};
+use crate::clvr::joint::{clvr_order_joint, enforce_joint_slippage, exact_input_legs, joint_objective};
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::multi_pool_model::MultiPoolModel;
+use crate::clvr::model::{Model, Omega};
+use crate::trades::implementation::Trade;
+use crate::trades::path::Hop;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, Address, U256};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    fn pools(n: usize) -> MultiPoolModel {
+        MultiPoolModel::new((0..n).map(|_| Box::new(CLVRModel::new(size(100), size(100))) as Box<dyn Model>).collect())
+    }
+
+    fn trade(amount: u128, direction: TradeDirection, pool: usize) -> Trade {
+        Trade::new(size(amount), direction).with_pool(pool)
+    }
+
+    #[test]
+    fn test_joint_single_pool() {
+        let trades = || {
+            Omega::new_from(vec![
+                trade(5, TradeDirection::Sell, 0),
+                trade(2, TradeDirection::Sell, 0),
+                trade(10, TradeDirection::Buy, 0),
+            ])
+        };
+        let model = CLVRModel::new(size(100), size(100));
+        let p_0 = size(1);
+
+        // with a single pool the joint objective is the CLVR objective
+        let mut greedy = trades();
+        let mut joint = trades();
+        let order = clvr_order(&model, p_0, &mut greedy, None, &Weights::default());
+        assert_eq!(clvr_order_joint(&pools(1), &[p_0], &mut joint, &[], &Weights::default()), order);
+    }
+
+    #[test]
+    fn test_joint_two_pools() {
+        let trades = || {
+            Omega::new_from(vec![
+                trade(10, TradeDirection::Sell, 0),
+                trade(10, TradeDirection::Sell, 1),
+                trade(10, TradeDirection::Buy, 0),
+                trade(10, TradeDirection::Buy, 1),
+            ])
+        };
+        let model = pools(2);
+        let p_0 = [size(1), size(1)];
+
+        // the trades of every pool compete for each position, so a trade bringing its pool back to p_0 goes before one
+        // moving the other pool away
+        let arrival = trades();
+        let mut joint = trades();
+        let order = clvr_order_joint(&model, &p_0, &mut joint, &[], &Weights::default());
+        assert_eq!(order, vec![3, 1, 4, 2]);
+
+        let weights = Weights::default();
+        assert!(joint_objective(&model, &p_0, &joint, &weights) < joint_objective(&model, &p_0, &arrival, &weights));
+    }
+
+    #[test]
+    fn test_multi_pool_model_after() {
+        let omega = Omega::new_from(vec![trade(10, TradeDirection::Sell, 0), trade(10, TradeDirection::Buy, 1)]);
+        let model = pools(2);
+        let single = CLVRModel::new(size(100), size(100));
+
+        // every trade moves its own pool only
+        let prices = model.after(&omega, 2).prices();
+        let sell = Omega::new_from(vec![trade(10, TradeDirection::Sell, 0)]);
+        let buy = Omega::new_from(vec![trade(10, TradeDirection::Buy, 0)]);
+        assert_eq!(prices, vec![single.P(&sell, 1), single.P(&buy, 1)]);
+        assert_eq!(model.after(&omega, 1).prices()[1], size(1));
+    }
+
+    #[test]
+    fn test_joint_path_legs() {
+        let mut omega = Omega::new_from(vec![
+            trade(10, TradeDirection::Sell, 0),
+            trade(10, TradeDirection::Sell, 1),
+            trade(10, TradeDirection::Buy, 0),
+            trade(10, TradeDirection::Buy, 1),
+        ]);
+        let (first, second) = (omega.id(2), omega.id(3));
+
+        // on their own the buy on pool 0 goes first, as the second leg of a path it follows the sell on pool 1
+        clvr_order_joint(&pools(2), &[size(1), size(1)], &mut omega, &[vec![first, second]], &Weights::default());
+        assert_eq!(omega.position(second), omega.position(first).map(|i| i + 1));
+
+        // a leg whose previous leg left the batch is ordered on its own
+        omega.remove_id(first);
+        clvr_order_joint(&pools(2), &[size(1), size(1)], &mut omega, &[vec![first, second]], &Weights::default());
+        assert_eq!(omega.len(), 3);
+    }
+
+    #[test]
+    fn test_exact_input_legs() {
+        let (a, b, c) = (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let hops = vec![
+            Hop { token_in: a, token_out: b, fee: U24::from(500) },
+            Hop { token_in: b, token_out: c, fee: U24::from(3000) },
+        ];
+        let model = pools(2);
+        let legs = exact_input_legs(&model, &hops, &[0, 1], size(10), size(5));
+
+        // the second leg sells what the first one is quoted to buy, the minimum output is checked at the end
+        assert_eq!(legs.len(), 2);
+        assert_eq!(legs[0].get_amount_in(), size(10));
+        assert_eq!(legs[1].get_amount_in(), model.simulate(&legs[0]).1);
+        assert_eq!(legs[0].get_amount_out_minimum(), U256::ZERO);
+        assert_eq!(legs[1].get_amount_out_minimum(), size(5));
+        assert_eq!((legs[0].get_pool(), legs[1].get_pool()), (0, 1));
+        assert_eq!(legs[1].get_fee(), U24::from(3000));
+    }
+
+    #[test]
+    fn test_joint_slippage_defers_reverting() {
+        // selling 10 x yields ~9.09 y on a fresh pool, ~8.33 y once the other sell on pool 0 is executed
+        let mut omega = Omega::new_from(vec![
+            trade(10, TradeDirection::Sell, 0),
+            trade(10, TradeDirection::Sell, 1).with_amount_out_minimum(size(9)),
+            trade(10, TradeDirection::Sell, 0).with_amount_out_minimum(size(9)),
+        ]);
+        let late = omega.id(3);
+
+        let (quotes, deferred) = enforce_joint_slippage(&pools(2), &mut omega, &[]);
+
+        // only the trade behind a trade of its own pool falls short, the others keep their order
+        assert_eq!(deferred.len(), 1);
+        assert_eq!(deferred[0].0, late);
+        assert_eq!(omega.len(), 2);
+        assert_eq!((omega[1].get_pool(), omega[2].get_pool()), (0, 1));
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[1].amount_out, pools(1).simulate(&trade(10, TradeDirection::Sell, 0)).1);
+        assert!(quotes[1].amount_out > size(9));
+    }
+    #[test]
+    fn test_joint_slippage_defers_paths() {
+        // the second leg of the path falls short once the sell on pool 1 is executed, the whole path is deferred
+        let mut omega = Omega::new_from(vec![
+            trade(10, TradeDirection::Sell, 1),
+            trade(10, TradeDirection::Sell, 0),
+            trade(10, TradeDirection::Sell, 1).with_amount_out_minimum(size(9)),
+            trade(10, TradeDirection::Sell, 0),
+        ]);
+        let path = vec![omega.id(2), omega.id(3)];
+        let last = omega.id(4);
+
+        let (quotes, deferred) = enforce_joint_slippage(&pools(2), &mut omega, std::slice::from_ref(&path));
+
+        assert_eq!(deferred.iter().map(|(id, _)| *id).collect::<Vec<_>>(), path);
+        assert_eq!(omega.len(), 2);
+        assert_eq!(omega.id(2), last);
+        // the trade after the path is quoted as if the first leg was never executed
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[1].amount_out, pools(1).simulate(&trade(10, TradeDirection::Sell, 0)).1);
+    }
+}
@@ -0,0 +1,191 @@
+use crate::clvr::algorithm::{weighted_objective, Weights};
+use crate::clvr::model::{Model, Omega};
+use crate::trades::ITrade;
+use alloy::primitives::U256;
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+// Starting annealing temperature, relative to the objective of the ordering being refined
+const INITIAL_TEMPERATURE: f64 = 0.05;
+
+// A neighbourhood move over positions of omega (1-indexed)
+enum Move {
+    Swap(usize, usize),    // exchanges the trades at the two positions
+    Reverse(usize, usize), // 2-opt: reverses the segment between the two positions (inclusive)
+    Insert(usize, usize),  // removes the trade at the first position and reinserts it at the second
+}
+
+impl Move {
+    // every move is expressed as a sequence of position swaps, so that it can be mirrored on any sequence
+    fn apply(&self, swap: &mut impl FnMut(usize, usize)) {
+        match *self {
+            Move::Swap(a, b) => swap(a, b),
+            Move::Reverse(a, b) => {
+                let (mut i, mut j) = (a, b);
+                while i < j {
+                    swap(i, j);
+                    i += 1;
+                    j -= 1;
+                }
+            }
+            Move::Insert(a, b) => {
+                if a < b {
+                    for k in a..b {
+                        swap(k, k + 1);
+                    }
+                } else {
+                    for k in (b..a).rev() {
+                        swap(k, k + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    fn inverse(&self) -> Move {
+        match *self {
+            Move::Swap(a, b) => Move::Swap(a, b),
+            Move::Reverse(a, b) => Move::Reverse(a, b),
+            Move::Insert(a, b) => Move::Insert(b, a),
+        }
+    }
+
+    // all swaps, reversals and insertions over a sequence of the given size
+    fn neighbourhood(size: usize) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for a in 1..size + 1 {
+            for b in a + 1..size + 1 {
+                moves.push(Move::Swap(a, b));
+                if b > a + 1 {
+                    moves.push(Move::Reverse(a, b)); // reversing two adjacent trades is a swap
+                }
+                moves.push(Move::Insert(a, b));
+                moves.push(Move::Insert(b, a));
+            }
+        }
+
+        moves
+    }
+
+    fn random(size: usize, rng: &mut impl Rng) -> Move {
+        let a = rng.gen_range(1..size + 1);
+        let mut b = rng.gen_range(1..size);
+        if b >= a {
+            b += 1; // b is drawn from every position except a
+        }
+
+        match rng.gen_range(0..3) {
+            0 => Move::Swap(a.min(b), a.max(b)),
+            1 => Move::Reverse(a.min(b), a.max(b)),
+            _ => Move::Insert(a, b),
+        }
+    }
+}
+
+// whether every trade stays within max_displacement positions of its arrival slot, where order[p - 1] is the trade
+// of the ordering being refined now at position p, and arrival[k - 1] the arrival slot of its k'th trade
+fn within(max_displacement: Option<(usize, &[usize])>, order: &[usize]) -> bool {
+    max_displacement
+        .is_none_or(|(k, arrival)| order.iter().enumerate().all(|(p, &i)| arrival[i - 1].abs_diff(p + 1) <= k))
+}
+
+// Improves an ordering (e.g. the output of clvr_order) within the given time budget.
+// First applies improving swaps, 2-opt reversals and insertions until none is left,
+// then spends the rest of the budget on simulated annealing, keeping the best ordering seen.
+// Moves taking a trade further than max_displacement from its arrival slot (as returned by clvr_order) are rejected
+pub fn clvr_refine<T: ITrade>(
+    model: &dyn Model,
+    p_0: U256,
+    omega: &mut Omega<T>,
+    budget: Duration,
+    max_displacement: Option<(usize, &[usize])>,
+    weights: &Weights,
+) {
+    let size = omega.len();
+    if size < 2 {
+        return;
+    }
+
+    let deadline = Instant::now() + budget;
+    let mut current = weighted_objective(model, p_0, omega, weights);
+    let mut order: Vec<usize> = (1..size + 1).collect(); // which trade of the input sits at each position
+
+    // local descent
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for m in Move::neighbourhood(size) {
+            if Instant::now() >= deadline {
+                return;
+            }
+
+            m.apply(&mut |i, j| {
+                omega.swap(i, j);
+                order.swap(i - 1, j - 1);
+            });
+            if within(max_displacement, &order) {
+                let value = weighted_objective(model, p_0, omega, weights);
+                if value < current {
+                    current = value;
+                    improved = true;
+                    continue;
+                }
+            }
+            m.inverse().apply(&mut |i, j| {
+                omega.swap(i, j);
+                order.swap(i - 1, j - 1);
+            });
+        }
+    }
+
+    // simulated annealing
+    let mut rng = rand::thread_rng();
+    let start = Instant::now();
+    let remaining = deadline.saturating_duration_since(start).as_secs_f64();
+    let initial_temperature = INITIAL_TEMPERATURE * current.to_f64().abs(); // the weighted objective may be negative
+
+    let mut best_order = order.clone();
+    let mut best = current.clone();
+
+    while Instant::now() < deadline {
+        let progress = start.elapsed().as_secs_f64() / remaining;
+        let temperature = initial_temperature * (1.0 - progress).max(0.0);
+
+        let m = Move::random(size, &mut rng);
+        m.apply(&mut |i, j| {
+            omega.swap(i, j);
+            order.swap(i - 1, j - 1);
+        });
+
+        if !within(max_displacement, &order) {
+            m.inverse().apply(&mut |i, j| {
+                omega.swap(i, j);
+                order.swap(i - 1, j - 1);
+            });
+            continue;
+        }
+
+        let value = weighted_objective(model, p_0, omega, weights);
+        let delta = (value.clone() - current.clone()).to_f64();
+        if delta < 0.0 || (temperature > 0.0 && rng.gen::<f64>() < (-delta / temperature).exp()) {
+            current = value;
+            if current < best {
+                best = current.clone();
+                best_order = order.clone();
+            }
+        } else {
+            m.inverse().apply(&mut |i, j| {
+                omega.swap(i, j);
+                order.swap(i - 1, j - 1);
+            });
+        }
+    }
+
+    // move back to the best ordering seen, position[k] is where trade k of the input sits now
+    let mut position = vec![0; size + 1];
+    for (p, &k) in order.iter().enumerate() {
+        position[k] = p + 1;
+    }
+    let target: Vec<usize> = best_order.iter().map(|&k| position[k]).collect();
+    omega.reorder(&target);
+}
@@ -0,0 +1,60 @@
+use crate::clvr::algorithm::{clvr_order, objective, Weights};
+use crate::clvr::local_search::clvr_refine;
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::trades::implementation::Trade;
+use crate::trades::TradeDirection;
+use alloy::primitives::U256;
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::clvr::model::Omega;
+
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let x = x.to_string();
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    #[test]
+    fn test_clvr_refine() {
+        let trades: Vec<(u128, TradeDirection)> = vec![
+            (20, TradeDirection::Buy),
+            (3, TradeDirection::Sell),
+            (7, TradeDirection::Sell),
+            (1, TradeDirection::Buy),
+            (12, TradeDirection::Sell),
+            (4, TradeDirection::Buy),
+            (9, TradeDirection::Sell),
+            (6, TradeDirection::Buy),
+        ];
+
+        let mut omega = Omega::new();
+        for (amount, direction) in &trades {
+            omega.push(Box::new(Trade::new(size(*amount), direction.clone())));
+        }
+
+        let model = CLVRModel::new(size(100), size(100));
+        let p_0 = size(1);
+
+        clvr_order(&model, p_0, &mut omega, None, &Weights::default());
+        let greedy_value = objective(&model, p_0, &omega);
+
+        clvr_refine(&model, p_0, &mut omega, Duration::from_millis(200), None, &Weights::default());
+        let refined_value = objective(&model, p_0, &omega);
+
+        println!(
+            "greedy {:.6}, refined {:.6}",
+            greedy_value.to_f64(),
+            refined_value.to_f64()
+        );
+
+        assert!(refined_value <= greedy_value);
+        assert_eq!(omega.len(), trades.len());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::clvr::algorithm::{ln, objective, Weights};
+use crate::clvr::model::{human_price, Model, Omega};
+use crate::clvr::netting::Fill;
+use crate::clvr::real::{self, Real};
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::U256;
+use serde::Serialize;
+
+// Metrics of a single ordering of a batch
+#[derive(Debug, Serialize)]
+pub struct BatchMetrics {
+    pub total_deviation: f64,   // sum over t of ( ln(p_0) - ln(P(o, t)) )^2
+    pub max_deviation: f64,     // max over t of | ln(p_0) - ln(P(o, t)) |
+    pub realized_variance: f64, // sum over t of ( ln(P(o, t)) - ln(P(o, t - 1)) )^2
+    pub execution_prices: Vec<Option<U256>>, // whole tokens y per whole token x of each trade, None if nothing was received
+    pub quotes: Vec<Quote>,
+    pub surplus: f64,        // sum over trades of ln(execution price / p_0), signed so that a gain is positive
+    pub worst_slippage: f64, // max over trades of ln(p_0 / execution price)
+    pub objective: f64,      // the weighted objective of the strategy
+}
+
+// Expected fill of a single trade of the batch
+#[derive(Debug, Serialize)]
+pub struct Quote {
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub partial: bool, // the trade reaches its price limit before being fully filled
+}
+
+impl Quote {
+    // fill of trade exchanging amount_in for amount_out
+    pub fn new(trade: &dyn ITrade, amount_in: U256, amount_out: U256) -> Self {
+        let partial = match trade.get_amount_out() {
+            None => amount_in < trade.get_amount_in(),
+            Some(requested) => amount_out < requested,
+        };
+
+        Quote {
+            amount_in,
+            amount_out,
+            partial,
+        }
+    }
+}
+
+impl BatchMetrics {
+    // quotes are the fills of the trades of omega, which depend on how the strategy executes the batch
+    pub fn new<T: ITrade>(model: &dyn Model, p_0: U256, omega: &Omega<T>, quotes: Vec<Quote>, weights: &Weights) -> Self {
+        let ln_p0 = ln(p_0);
+        let surpluses: Vec<f64> = (1..omega.len() + 1)
+            .filter_map(|t| relative_surplus(&quotes[t - 1], omega[t].get_direction(), &ln_p0, model.decimals()))
+            .map(|s| s.to_f64())
+            .collect();
+        let surplus: f64 = surpluses.iter().sum();
+        let worst_slippage = surpluses.iter().map(|s| -s).fold(None, |w: Option<f64>, s| Some(w.map_or(s, |w| w.max(s))));
+        let total_deviation = objective(model, p_0, omega).to_f64();
+
+        BatchMetrics {
+            total_deviation,
+            max_deviation: max_deviation(model, p_0, omega).to_f64(),
+            realized_variance: realized_variance(model, omega).to_f64(),
+            execution_prices: (1..omega.len() + 1)
+                .map(|t| execution_price(&quotes[t - 1], omega[t].get_direction(), model.decimals()))
+                .collect(),
+            quotes,
+            surplus,
+            worst_slippage: worst_slippage.unwrap_or(0.0),
+            objective: weights.volatility * total_deviation - weights.surplus * surplus
+                + weights.slippage * worst_slippage.unwrap_or(0.0),
+        }
+    }
+}
+
+// Metrics of an executed batch next to the metrics the batch would have had in arrival order
+#[derive(Debug, Serialize)]
+pub struct BatchReport {
+    pub strategy: String,
+    pub weights: Weights,
+    pub ordered: BatchMetrics,
+    pub fifo: BatchMetrics,
+    pub deferred: usize, // trades left out of the batch since they would revert
+    pub fills: Vec<Fill>, // internal match and pool split of every trade in arrival order, empty without netting
+}
+
+pub fn max_deviation<T: ITrade>(model: &dyn Model, p_0: U256, omega: &Omega<T>) -> Real {
+    let ln_p0 = ln(p_0);
+    let mut max = real::zero();
+
+    for t in 1..omega.len() + 1 {
+        let deviation = (ln_p0.clone() - ln(model.P(omega, t))).abs();
+        if deviation > max {
+            max = deviation;
+        }
+    }
+
+    max
+}
+
+// realized variance of the price path P(o, 0), P(o, 1), ..., P(o, n)
+pub fn realized_variance<T: ITrade>(model: &dyn Model, omega: &Omega<T>) -> Real {
+    let mut variance = real::zero();
+    let mut ln_previous = ln(model.P(omega, 0));
+
+    for t in 1..omega.len() + 1 {
+        let ln_current = ln(model.P(omega, t));
+        variance += (ln_current.clone() - ln_previous).square();
+        ln_previous = ln_current;
+    }
+
+    variance
+}
+
+// price a trade with quote executes at, in the same units as P
+pub fn execution_price(quote: &Quote, direction: TradeDirection, decimals: (u8, u8)) -> Option<U256> {
+    match direction {
+        TradeDirection::Sell => {
+            if quote.amount_in.is_zero() {
+                return None;
+            }
+            Some(human_price(quote.amount_out, quote.amount_in, decimals))
+        }
+        TradeDirection::Buy => {
+            if quote.amount_out.is_zero() {
+                return None;
+            }
+            Some(human_price(quote.amount_in, quote.amount_out, decimals))
+        }
+    }
+}
+
+// ln(execution price / p_0) of a trade with quote, signed so that a gain is positive. None if nothing is exchanged
+pub fn relative_surplus(quote: &Quote, direction: TradeDirection, ln_p0: &Real, decimals: (u8, u8)) -> Option<Real> {
+    let price = execution_price(quote, direction.clone(), decimals).filter(|price| !price.is_zero())?;
+    let ln_price = ln(price);
+
+    Some(match direction {
+        TradeDirection::Sell => ln_price - ln_p0,
+        TradeDirection::Buy => ln_p0.clone() - ln_price,
+    })
+}
+
+// amounts in and out of the t'th trade, possibly partially filled
+pub fn quote<T: ITrade>(model: &dyn Model, omega: &Omega<T>, t: usize) -> Quote {
+    let amount_in = model.amount_in(omega, t);
+    let amount_out = match omega[t].get_direction() {
+        TradeDirection::Sell => model.y_out(omega, t),
+        TradeDirection::Buy => model.x_out(omega, t),
+    };
+
+    Quote::new(&omega[t], amount_in, amount_out)
+}
+
+// quotes of the trades of omega executed one after the other
+pub fn quotes<T: ITrade>(model: &dyn Model, omega: &Omega<T>) -> Vec<Quote> {
+    (1..omega.len() + 1).map(|t| quote(model, omega, t)).collect()
+}
@@ -1,16 +1,31 @@
-mod algorithm;
+pub mod algorithm;
+mod auction;
+mod exact;
+pub mod joint;
+mod local_search;
+pub mod metrics;
 pub mod model;
+pub mod netting;
+pub mod real;
+pub mod routing;
+pub mod slippage;
+pub mod strategy;
 
 #[cfg(test)]
 mod algorithm_tests;
-
-use alloy::sol;
-
-// Uniswap V4 SwapParams
-sol! {
-    struct SwapParams {
-        bool zeroForOne;
-        int256 amountSpecified;
-        uint160 sqrtPriceLimitX96;
-    }
-}
+#[cfg(test)]
+mod auction_tests;
+#[cfg(test)]
+mod exact_tests;
+#[cfg(test)]
+mod joint_tests;
+#[cfg(test)]
+mod local_search_tests;
+#[cfg(test)]
+mod netting_tests;
+#[cfg(test)]
+mod real_tests;
+#[cfg(test)]
+mod routing_tests;
+#[cfg(test)]
+mod slippage_tests;
@@ -1,10 +1,17 @@
-use crate::clvr::model::{Model, Omega};
-use crate::trades::TradeDirection;
-use alloy::primitives::U256;
+use crate::clvr::model::{human_price, Model, Trades};
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, U256};
+use uniswap_v3_sdk::prelude::Q96;
+
+// fees are expressed in hundredths of a bip
+const FEE_DENOMINATOR: u32 = 1_000_000;
 
 pub struct CLVRModel {
     reserve_x: U256,
     reserve_y: U256,
+    fee: Option<U24>, // LP fee of a pool charging it whatever the trade's fee tier, as a V2 pair
+    decimals_x: u8,
+    decimals_y: u8,
 }
 
 impl CLVRModel {
@@ -12,53 +19,148 @@ impl CLVRModel {
         CLVRModel {
             reserve_x,
             reserve_y,
+            fee: None,
+            decimals_x: 18,
+            decimals_y: 18,
+        }
+    }
+
+    pub fn with_decimals(mut self, decimals_x: u8, decimals_y: u8) -> Self {
+        self.decimals_x = decimals_x;
+        self.decimals_y = decimals_y;
+        self
+    }
+
+    pub fn with_fee(mut self, fee: U24) -> Self {
+        self.fee = Some(fee);
+        self
+    }
+
+    // LP fee charged on amount_in, it stays in the pool
+    fn lp_fee(&self, amount_in: U256, fee: U24) -> U256 {
+        let fee_complement = U256::from(FEE_DENOMINATOR) - U256::from(fee);
+        amount_in - amount_in * fee_complement / U256::from(FEE_DENOMINATOR)
+    }
+
+    // largest amount in after the LP fee before the pool's sqrt price, sqrt(Y / X) in Q64.96, reaches limit.
+    // The price stays on the curve X * Y = k, so reserve_in at the limit is sqrt(k) / limit or sqrt(k) * limit
+    fn max_in_less_fee(&self, limit: U256, zero_for_one: bool, reserve_in: U256, reserve_out: U256) -> U256 {
+        let root_k = (reserve_in * reserve_out).root(2);
+        let reserve_in_at_limit = if zero_for_one {
+            root_k * Q96 / limit
+        } else {
+            root_k * limit / Q96
+        };
+
+        reserve_in_at_limit.saturating_sub(reserve_in)
+    }
+
+    // executes trade against a pool with reserve_in and reserve_out, returns the amounts in and out.
+    // A trade reaching its price limit is partially filled.
+    // NOTE: an exact output trade asking for the whole reserve_out cannot be filled and leaves the pool untouched
+    fn swap(&self, trade: &dyn ITrade, zero_for_one: bool, reserve_in: &mut U256, reserve_out: &mut U256) -> (U256, U256) {
+        let fee = self.fee.unwrap_or(trade.get_fee());
+        let fee_complement = U256::from(FEE_DENOMINATOR) - U256::from(fee);
+        let max_less_fee = trade
+            .get_sqrt_price_limit_x96()
+            .map(|limit| self.max_in_less_fee(U256::from(limit), zero_for_one, *reserve_in, *reserve_out));
+
+        let (amount_in, amount_out) = match trade.get_amount_out() {
+            None => {
+                let mut amount_in = trade.get_amount_in();
+                let mut amount_less_fee = amount_in - self.lp_fee(amount_in, fee);
+                if let Some(max) = max_less_fee.filter(|&max| max < amount_less_fee) {
+                    amount_less_fee = max;
+                    amount_in = amount_in.min((max * U256::from(FEE_DENOMINATOR)).div_ceil(fee_complement));
+                }
+                (amount_in, amount_less_fee * *reserve_out / (*reserve_in + amount_less_fee))
+            }
+            Some(mut amount_out) => {
+                if let Some(max) = max_less_fee {
+                    amount_out = amount_out.min(max * *reserve_out / (*reserve_in + max));
+                }
+                if amount_out >= *reserve_out {
+                    return (U256::ZERO, U256::ZERO);
+                }
+
+                // smallest input, rounded up, whose output after the LP fee covers amount_out
+                let amount_less_fee = (*reserve_in * amount_out).div_ceil(*reserve_out - amount_out);
+                let amount_in = (amount_less_fee * U256::from(FEE_DENOMINATOR)).div_ceil(fee_complement);
+                (amount_in, amount_out)
+            }
+        };
+
+        *reserve_in += amount_in;
+        *reserve_out -= amount_out;
+
+        (amount_in, amount_out)
+    }
+
+    // executes trade against reserves x and y in the direction of the trade, returns the amounts in and out
+    fn step(&self, trade: &dyn ITrade, x: &mut U256, y: &mut U256) -> (U256, U256) {
+        match trade.get_direction() {
+            TradeDirection::Sell => self.swap(trade, true, x, y),
+            TradeDirection::Buy => self.swap(trade, false, y, x),
+        }
+    }
+
+    // reserves (X, Y) after the first i trades of o are executed, and the amounts in and out of the i'th trade.
+    // NOTE: computed iteratively, since the recursive definition re-evaluates every prefix of o
+    fn execute(&self, o: &dyn Trades, i: usize) -> ((U256, U256), (U256, U256)) {
+        let mut x = self.reserve_x;
+        let mut y = self.reserve_y;
+        let mut amounts = (U256::ZERO, U256::ZERO);
+
+        for t in 1..i + 1 {
+            amounts = self.step(o.trade(t), &mut x, &mut y);
         }
+
+        ((x, y), amounts)
     }
 }
 
 impl Model for CLVRModel {
-    fn y_out(&self, o: &Omega, i: usize) -> U256 {
-        if o[i].get_direction() == TradeDirection::Sell {
-            let fraction = self.Y(o, i - 1) / (self.X(o, i - 1) + o[i].get_amount_in());
-            return fraction * o[i].get_amount_in();
+    fn y_out(&self, o: &dyn Trades, i: usize) -> U256 {
+        if o.trade(i).get_direction() == TradeDirection::Sell {
+            return self.execute(o, i).1 .1;
         }
 
         U256::from(0)
     }
 
-    fn x_out(&self, o: &Omega, i: usize) -> U256 {
-        if o[i].get_direction() == TradeDirection::Buy {
-            let fraction = self.X(o, i - 1) / (self.Y(o, i - 1) + o[i].get_amount_in());
-            return fraction * o[i].get_amount_in();
+    fn x_out(&self, o: &dyn Trades, i: usize) -> U256 {
+        if o.trade(i).get_direction() == TradeDirection::Buy {
+            return self.execute(o, i).1 .1;
         }
 
         U256::from(0)
     }
 
-    fn Y(&self, o: &Omega, i: usize) -> U256 {
-        if i == 0 {
-            return self.reserve_y;
-        } else if i > 0 && o[i].get_direction() == TradeDirection::Buy {
-            return self.Y(o, i - 1) + o[i].get_amount_in();
-        } else if i > 0 && o[i].get_direction() == TradeDirection::Sell {
-            return self.Y(o, i - 1) - self.y_out(o, i);
-        }
-        panic!("Invalid call to Y");
+    fn amount_in(&self, o: &dyn Trades, i: usize) -> U256 {
+        self.execute(o, i).1 .0
     }
 
-    fn X(&self, o: &Omega, i: usize) -> U256 {
-        if i == 0 {
-            return self.reserve_x;
-        } else if i > 0 && o[i].get_direction() == TradeDirection::Sell {
-            return self.X(o, i - 1) + o[i].get_amount_in();
-        } else if i > 0 && o[i].get_direction() == TradeDirection::Buy {
-            return self.X(o, i - 1) - self.x_out(o, i);
-        }
-        panic!("Invalid call to X");
+    fn P(&self, o: &dyn Trades, i: usize) -> U256 {
+        let (x, y) = self.execute(o, i).0;
+        human_price(y, x, self.decimals())
+    }
+
+    fn after(&self, o: &dyn Trades, i: usize) -> Box<dyn Model> {
+        let (reserve_x, reserve_y) = self.execute(o, i).0;
+        Box::new(CLVRModel {
+            reserve_x,
+            reserve_y,
+            ..*self
+        })
+    }
+
+    fn simulate(&self, trade: &dyn ITrade) -> (U256, U256, U256) {
+        let (mut x, mut y) = (self.reserve_x, self.reserve_y);
+        let (amount_in, amount_out) = self.step(trade, &mut x, &mut y);
+        (amount_in, amount_out, human_price(y, x, self.decimals()))
     }
 
-    fn P(&self, o: &Omega, i: usize) -> U256 {
-        let base = U256::from_str_radix("1000000000000000000", 10).unwrap();
-        self.Y(o, i) * base / self.X(o, i)
+    fn decimals(&self) -> (u8, u8) {
+        (self.decimals_x, self.decimals_y)
     }
 }
@@ -0,0 +1,99 @@
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::{human_price, Model, Omega};
+use crate::trades::implementation::Trade;
+use crate::trades::TradeDirection;
+use crate::clvr::metrics::quote;
+use alloy::primitives::{aliases::U24, U160, U256};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let x = x.to_string();
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    #[test]
+    fn test_clvr_model_fees() {
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Sell).with_fee(U24::from(3000))));
+
+        // 0.3% of the input is taken before the swap: 9.97 * 100 / (100 + 9.97)
+        let amount_less_fee = size(997) / U256::from(100);
+        let expected_y_out = amount_less_fee * size(100) / (size(100) + amount_less_fee);
+
+        let model = CLVRModel::new(size(100), size(100));
+        assert_eq!(model.y_out(&omega, 1), expected_y_out);
+        // the LP fee stays in the pool, X is 110
+        assert_eq!(model.P(&omega, 1), human_price(size(100) - expected_y_out, size(110), (18, 18)));
+
+        // a V2 pair charges its fee whatever the trade's fee tier
+        let model = CLVRModel::new(size(100), size(100)).with_fee(U24::from(3000));
+        let untiered = Trade::new(size(10), TradeDirection::Sell);
+        assert_eq!(model.simulate(&untiered).1, expected_y_out);
+    }
+
+    #[test]
+    fn test_clvr_model_decimals() {
+        let omega: Omega = Omega::new();
+
+        // 1000 WETH (18 decimals) against 3,000,000 USDC (6 decimals)
+        let weth = size(1000);
+        let usdc = U256::from(3_000_000_000_000u64);
+
+        let model = CLVRModel::new(weth, usdc).with_decimals(18, 6);
+        assert_eq!(model.P(&omega, 0), size(3000));
+
+        let model = CLVRModel::new(usdc, weth).with_decimals(6, 18);
+        assert_eq!(model.P(&omega, 0), size(1) / U256::from(3000));
+    }
+
+    #[test]
+    fn test_clvr_model_exact_output() {
+        let model = CLVRModel::new(size(100), size(100));
+
+        let mut exact_output = Omega::new();
+        exact_output.push(Box::new(
+            Trade::new_exact_output(size(5), size(10), TradeDirection::Buy).with_fee(U24::from(3000)),
+        ));
+
+        // the output is fixed, and the computed input is the smallest one buying at least as much
+        let amount_in = model.amount_in(&exact_output, 1);
+        assert_eq!(model.x_out(&exact_output, 1), size(5));
+        assert_eq!(model.P(&exact_output, 1), human_price(size(100) + amount_in, size(95), (18, 18)));
+
+        let mut exact_input = Omega::new();
+        exact_input.push(Box::new(Trade::new(amount_in, TradeDirection::Buy).with_fee(U24::from(3000))));
+        assert!(model.x_out(&exact_input, 1) >= size(5));
+
+        let mut exact_input = Omega::new();
+        exact_input.push(Box::new(
+            Trade::new(amount_in - U256::from(1), TradeDirection::Buy).with_fee(U24::from(3000)),
+        ));
+        assert!(model.x_out(&exact_input, 1) < size(5));
+    }
+
+    #[test]
+    fn test_clvr_model_price_limit() {
+        // the sqrt price starts at 1 and the limit is 10 / 11, reached once X = sqrt(k) * 11 / 10 = 110
+        let limit = (U160::from(10) << 96) / U160::from(11);
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(size(50), TradeDirection::Sell).with_sqrt_price_limit_x96(limit)));
+        omega.push(Box::new(Trade::new(size(5), TradeDirection::Sell).with_sqrt_price_limit_x96(limit)));
+
+        let model = CLVRModel::new(size(100), size(100));
+        let fill = quote(&model, &omega, 1);
+        assert!(fill.partial);
+        assert!(fill.amount_in.abs_diff(size(10)) < U256::from(1_000_000_000u64));
+        assert!(model.P(&omega, 1).abs_diff(size(100) / U256::from(121)) < U256::from(1_000_000_000u64));
+
+        // once at the limit nothing more is filled
+        let fill = quote(&model, &omega, 2);
+        assert!(fill.partial);
+        assert!(fill.amount_in < U256::from(1_000_000_000u64));
+    }
+}
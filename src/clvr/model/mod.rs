@@ -2,6 +2,8 @@
 
 use crate::trades::ITrade;
 use alloy::primitives::U256;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::{
     fmt::{self, Formatter},
@@ -9,51 +11,179 @@ use std::{
 };
 
 pub mod clvr_model;
+pub mod multi_pool_model;
+pub mod stable_swap_model;
+pub mod v3_model;
+
+#[cfg(test)]
+mod clvr_model_tests;
+#[cfg(test)]
+mod omega_tests;
+#[cfg(test)]
+mod stable_swap_model_tests;
+#[cfg(test)]
+mod v3_model_tests;
+
+// Identity of a trade within an ordering, kept while the trade is moved around
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TradeId(pub u64);
+
+// a trade of a serialized ordering
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    id: TradeId,
+    trade: T,
+}
 
 // Notation for a particular trades ordering.
 // NOTE: Omega is 1-indexed
-pub struct Omega(Vec<Box<dyn ITrade>>);
+pub struct Omega<T: ITrade = Box<dyn ITrade>> {
+    entries: Vec<(TradeId, T)>,
+    next_id: u64,
+}
 
-impl Omega {
+impl<T: ITrade> Omega<T> {
     pub fn new() -> Self {
-        Omega(Vec::new())
+        Omega {
+            entries: Vec::new(),
+            next_id: 1,
+        }
     }
 
-    #[cfg(test)]
-    pub fn new_from(vec: Vec<Box<dyn ITrade>>) -> Self {
-        Omega(vec)
+    // trades in their order of execution, numbered 1, 2, ...
+    pub fn new_from(vec: Vec<T>) -> Self {
+        vec.into_iter().collect()
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
     pub fn swap(&mut self, index1: usize, index2: usize) {
-        self.0.swap(index1 - 1, index2 - 1); // 1-indexed
+        self.entries.swap(index1 - 1, index2 - 1); // 1-indexed
+    }
+
+    pub fn push(&mut self, trade: T) -> TradeId {
+        let id = self.new_id();
+        self.entries.push((id, trade));
+        id
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        self.entries.remove(index - 1).1 // 1-indexed
+    }
+
+    // removes the trade with identity id, wherever it was moved to
+    pub fn remove_id(&mut self, id: TradeId) -> Option<T> {
+        self.position(id).map(|index| self.remove(index))
+    }
+
+    // inserts trade at position index, shifting the trades after it
+    pub fn insert(&mut self, index: usize, trade: T) -> TradeId {
+        let id = self.new_id();
+        self.entries.insert(index - 1, (id, trade)); // 1-indexed
+        id
+    }
+
+    // moves the trade at position from to position to, shifting the trades in between. Keeps its identity
+    pub fn move_to(&mut self, from: usize, to: usize) {
+        let entry = self.entries.remove(from - 1);
+        self.entries.insert(to - 1, entry); // 1-indexed
+    }
+
+    pub fn id(&self, index: usize) -> TradeId {
+        self.entries[index - 1].0 // 1-indexed
+    }
+
+    // position of the trade with identity id
+    pub fn position(&self, id: TradeId) -> Option<usize> {
+        self.entries.iter().position(|(entry_id, _)| *entry_id == id).map(|i| i + 1) // 1-indexed
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.entries.iter().map(|(_, trade)| trade)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.iter_mut().map(|(_, trade)| trade)
+    }
+
+    // identities of the trades in their order of execution
+    pub fn ids(&self) -> impl Iterator<Item = TradeId> + '_ {
+        self.entries.iter().map(|(id, _)| *id)
+    }
+
+    // Rearranges the trades so that position t holds the trade currently at position order[t - 1].
+    // NOTE: order is a permutation of 1..=len (1-indexed)
+    pub fn reorder(&mut self, order: &[usize]) {
+        let mut entries: Vec<Option<(TradeId, T)>> = self.entries.drain(..).map(Some).collect();
+        self.entries = order
+            .iter()
+            .map(|&i| entries[i - 1].take().expect("order must be a permutation"))
+            .collect();
+    }
+
+    fn new_id(&mut self) -> TradeId {
+        self.next_id += 1;
+        TradeId(self.next_id - 1)
+    }
+}
+
+impl<T: ITrade> Default for Omega<T> {
+    fn default() -> Self {
+        Omega::new()
+    }
+}
+
+impl<T: ITrade> FromIterator<T> for Omega<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut omega = Omega::new();
+        for trade in iter {
+            omega.push(trade);
+        }
+        omega
+    }
+}
+
+impl<T: ITrade> IntoIterator for Omega<T> {
+    type Item = T;
+    type IntoIter = std::iter::Map<std::vec::IntoIter<(TradeId, T)>, fn((TradeId, T)) -> T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter().map(|(_, trade)| trade)
     }
+}
+
+impl<'a, T: ITrade> IntoIterator for &'a Omega<T> {
+    type Item = &'a T;
+    type IntoIter = std::iter::Map<std::slice::Iter<'a, (TradeId, T)>, fn(&'a (TradeId, T)) -> &'a T>;
 
-    pub fn push(&mut self, trade: Box<dyn ITrade>) {
-        self.0.push(trade);
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(_, trade)| trade)
     }
 }
 
-impl Index<usize> for Omega {
-    type Output = Box<dyn ITrade>;
+impl<T: ITrade> Index<usize> for Omega<T> {
+    type Output = T;
 
     // 1-indexed
     fn index(&self, i: usize) -> &Self::Output {
-        &self.0[i - 1]
+        &self.entries[i - 1].1
     }
 }
 
-impl Debug for Omega {
+impl<T: ITrade> Debug for Omega<T> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        for i in 1..self.len() + 1 {
-            write!(
+        for trade in self {
+            writeln!(
                 f,
-                "{:?} {:?}, \n",
-                self[i].get_direction(),
-                self[i].get_amount_in()
+                "{:?} {:?},",
+                trade.get_direction(),
+                trade.get_amount_in()
             )?;
         }
 
@@ -61,8 +191,9 @@ impl Debug for Omega {
     }
 }
 
-impl PartialEq for Omega {
-    fn eq(&self, other: &Self) -> bool {
+// same trades in the same order, whatever their types and identities
+impl<T: ITrade, U: ITrade> PartialEq<Omega<U>> for Omega<T> {
+    fn eq(&self, other: &Omega<U>) -> bool {
         if self.len() != other.len() {
             return false;
         }
@@ -75,16 +206,88 @@ impl PartialEq for Omega {
             }
         }
 
-        return true;
+        true
     }
 }
 
-pub trait Model {
-    fn y_out(&self, o: &Omega, i: usize) -> U256;
-    fn x_out(&self, o: &Omega, i: usize) -> U256;
+// Serialized as the list of trades in their order of execution, each with its identity
+impl<T: ITrade + Serialize> Serialize for Omega<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.entries.iter().map(|(id, trade)| Entry { id: *id, trade }))
+    }
+}
+
+impl<'de, T: ITrade + Deserialize<'de>> Deserialize<'de> for Omega<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries: Vec<Entry<T>> = Vec::deserialize(deserializer)?;
+        let mut ids = HashSet::new();
+        if !entries.iter().all(|entry| ids.insert(entry.id)) {
+            return Err(de::Error::custom("duplicate trade id"));
+        }
+
+        Ok(Omega {
+            next_id: entries.iter().map(|entry| entry.id.0 + 1).max().unwrap_or(1),
+            entries: entries.into_iter().map(|entry| (entry.id, entry.trade)).collect(),
+        })
+    }
+}
+
+// Read access to an ordering of trades of any type, which keeps Model object safe
+pub trait Trades: Sync {
+    fn len(&self) -> usize;
+    fn trade(&self, i: usize) -> &dyn ITrade; // 1-indexed
+}
+
+impl<T: ITrade> Trades for Omega<T> {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn trade(&self, i: usize) -> &dyn ITrade {
+        &self[i]
+    }
+}
+
+// a single trade, as an ordering, e.g. to step a model one trade at a time with after
+pub struct SingleTrade<'a>(pub &'a dyn ITrade);
+
+impl Trades for SingleTrade<'_> {
+    fn len(&self) -> usize {
+        1
+    }
+
+    fn trade(&self, _: usize) -> &dyn ITrade {
+        self.0
+    }
+}
+
+pub trait Model: Send + Sync {
+    fn y_out(&self, o: &dyn Trades, i: usize) -> U256;
+    fn x_out(&self, o: &dyn Trades, i: usize) -> U256;
+    fn amount_in(&self, o: &dyn Trades, i: usize) -> U256; // amount paid by the i'th trade, computed for exact output trades
+
+    fn P(&self, o: &dyn Trades, i: usize) -> U256; // price of a whole token x in whole tokens y, scaled by 10 ** 18
+
+    // model of the pool once the first i trades of o are executed
+    fn after(&self, o: &dyn Trades, i: usize) -> Box<dyn Model>;
+    // executes trade alone against the pool, returns the amounts in and out and P afterwards. Leaves the model untouched
+    fn simulate(&self, trade: &dyn ITrade) -> (U256, U256, U256);
+
+    // whether the pool rejects the i'th trade of o whatever its amounts, once the trades before it are executed
+    fn reverts(&self, _o: &dyn Trades, _i: usize) -> bool {
+        false
+    }
+
+    // decimals of tokens x and y, reserves and trade amounts are in raw units of the tokens
+    fn decimals(&self) -> (u8, u8) {
+        (18, 18)
+    }
+}
 
-    fn Y(&self, o: &Omega, i: usize) -> U256;
-    fn X(&self, o: &Omega, i: usize) -> U256;
+// Price of a whole token x in whole tokens y scaled by 10 ** 18, given raw amounts of both tokens
+pub fn human_price(amount_y: U256, amount_x: U256, decimals: (u8, u8)) -> U256 {
+    let (decimals_x, decimals_y) = decimals;
+    let ten = U256::from(10);
 
-    fn P(&self, o: &Omega, i: usize) -> U256;
+    amount_y * ten.pow(U256::from(18 + decimals_x as u64)) / (amount_x * ten.pow(U256::from(decimals_y)))
 }
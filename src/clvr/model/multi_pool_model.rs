@@ -0,0 +1,70 @@
+use crate::clvr::model::{Model, Omega, SingleTrade, Trades};
+use crate::trades::ITrade;
+use alloy::primitives::U256;
+
+// Linked pools sharing a token (e.g. USDC/WETH and USDT/WETH), each trade is executed against the pool given by its
+// get_pool. A trade only moves the price of its own pool, arbitrage between the pools is not modelled
+// (see clvr_order_joint)
+pub struct MultiPoolModel {
+    pools: Vec<Box<dyn Model>>,
+}
+
+// the trades of an ordering executed against a single pool, in order
+struct PoolTrades<'a> {
+    o: &'a dyn Trades,
+    positions: Vec<usize>,
+}
+
+impl Trades for PoolTrades<'_> {
+    fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn trade(&self, i: usize) -> &dyn ITrade {
+        self.o.trade(self.positions[i - 1]) // 1-indexed
+    }
+}
+
+impl MultiPoolModel {
+    pub fn new(pools: Vec<Box<dyn Model>>) -> Self {
+        MultiPoolModel { pools }
+    }
+
+    pub fn pool(&self, k: usize) -> &dyn Model {
+        self.pools[k].as_ref()
+    }
+
+    // price of every pool before any trade, as Model::P
+    pub fn prices(&self) -> Vec<U256> {
+        let empty: Omega = Omega::new();
+        self.pools.iter().map(|pool| pool.P(&empty, 0)).collect()
+    }
+
+    // models of the pools once the first i trades of o are executed, each against its own pool
+    pub fn after(&self, o: &dyn Trades, i: usize) -> MultiPoolModel {
+        let pools = self
+            .pools
+            .iter()
+            .enumerate()
+            .map(|(k, pool)| {
+                let trades = PoolTrades {
+                    o,
+                    positions: (1..i + 1).filter(|&t| o.trade(t).get_pool() == k).collect(),
+                };
+                pool.after(&trades, trades.len())
+            })
+            .collect();
+
+        MultiPoolModel { pools }
+    }
+
+    // models of the pools once trade is executed against its pool
+    pub fn after_trade(&self, trade: &dyn ITrade) -> MultiPoolModel {
+        self.after(&SingleTrade(trade), 1)
+    }
+
+    // executes trade alone against its pool, returns the amounts in and out and the pool's P afterwards
+    pub fn simulate(&self, trade: &dyn ITrade) -> (U256, U256, U256) {
+        self.pool(trade.get_pool()).simulate(trade)
+    }
+}
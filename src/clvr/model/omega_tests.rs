@@ -0,0 +1,69 @@
+use crate::clvr::model::{Omega, TradeId};
+use crate::trades::implementation::Trade;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::U256;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trades() -> Omega<Trade> {
+        Omega::new_from(vec![
+            Trade::new(U256::from(1), TradeDirection::Sell),
+            Trade::new(U256::from(2), TradeDirection::Buy),
+            Trade::new(U256::from(3), TradeDirection::Sell),
+        ])
+    }
+
+    fn amounts<T: ITrade>(omega: &Omega<T>) -> Vec<u64> {
+        omega.iter().map(|trade| trade.get_amount_in().to::<u64>()).collect()
+    }
+
+    #[test]
+    fn test_omega_ids() {
+        let mut omega = trades();
+        let ids: Vec<TradeId> = omega.ids().collect();
+
+        // identities follow the trades as they are moved around
+        omega.swap(1, 3);
+        omega.move_to(1, 2);
+        assert_eq!(amounts(&omega), vec![2, 3, 1]);
+        assert_eq!(omega.ids().collect::<Vec<_>>(), vec![ids[1], ids[2], ids[0]]);
+        omega.reorder(&[3, 1, 2]);
+        assert_eq!(amounts(&omega), vec![1, 2, 3]);
+        assert_eq!(omega.ids().collect::<Vec<_>>(), ids);
+
+        // removed trades are found by identity, new trades never reuse one
+        assert_eq!(omega.remove_id(ids[1]).unwrap().get_amount_in(), U256::from(2));
+        assert!(omega.remove_id(ids[1]).is_none());
+        let id = omega.push(Trade::new(U256::from(4), TradeDirection::Buy));
+        assert!(!ids.contains(&id));
+        assert_eq!(omega.position(id), Some(3));
+        assert_eq!(omega.id(2), ids[2]);
+
+        let rest: Vec<Trade> = omega.into_iter().collect();
+        assert_eq!(rest.len(), 3);
+    }
+
+    #[test]
+    fn test_omega_serde() {
+        let mut omega = trades();
+        omega.swap(1, 2);
+        let ids: Vec<TradeId> = omega.ids().collect();
+
+        let json = serde_json::to_string(&omega).unwrap();
+        let mut restored: Omega<Trade> = serde_json::from_str(&json).unwrap();
+        assert!(restored == omega);
+        assert_eq!(restored.ids().collect::<Vec<_>>(), ids);
+        let id = restored.push(Trade::new(U256::from(4), TradeDirection::Buy));
+        assert!(!ids.contains(&id));
+
+        // boxed trades are stored as Trade
+        let boxed: Omega = serde_json::from_str(&json).unwrap();
+        assert!(boxed == omega);
+        assert_eq!(serde_json::to_string(&boxed).unwrap(), json);
+
+        let duplicate = json.replace(&format!("\"id\":{}", ids[1].0), &format!("\"id\":{}", ids[0].0));
+        assert!(serde_json::from_str::<Omega<Trade>>(&duplicate).is_err());
+    }
+}
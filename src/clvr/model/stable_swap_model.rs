@@ -0,0 +1,214 @@
+use crate::clvr::model::{Model, Trades};
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, U256};
+
+// fees are expressed in hundredths of a bip
+const FEE_DENOMINATOR: u32 = 1_000_000;
+const N_COINS: u64 = 2;
+const MAX_ITERATIONS: usize = 255;
+
+// Curve StableSwap pool of two coins: A * n^n * S + D = A * n^n * D + D^(n+1) / (n^n * x * y), S = x + y, on balances
+// normalized to 18 decimals, or to the decimals of the coin with more. D and the balance of a coin given the other are found by Newton iteration, as the pool does.
+// The fee is taken from the output, in the pool's own fee tier.
+// NOTE: sqrt price limits of trades are not modelled, a stable pool has no sqrt price
+pub struct StableSwapModel {
+    reserve_x: U256,
+    reserve_y: U256,
+    amplification: U256, // A
+    fee: U24,
+    decimals_x: u8,
+    decimals_y: u8,
+}
+
+impl StableSwapModel {
+    // A is at least 1 in a Curve pool, the curve is degenerate at 0
+    pub fn new(reserve_x: U256, reserve_y: U256, amplification: u64, fee: U24) -> Self {
+        StableSwapModel {
+            reserve_x,
+            reserve_y,
+            amplification: U256::from(amplification.max(1)),
+            fee,
+            decimals_x: 18,
+            decimals_y: 18,
+        }
+    }
+
+    pub fn with_decimals(mut self, decimals_x: u8, decimals_y: u8) -> Self {
+        self.decimals_x = decimals_x;
+        self.decimals_y = decimals_y;
+        self
+    }
+
+    // A * n, the pool's Ann
+    fn ann(&self) -> U256 {
+        self.amplification * U256::from(N_COINS)
+    }
+
+    // factor normalizing raw amounts of a coin with the given decimals to the decimals of both coins
+    fn rate(&self, decimals: u8) -> U256 {
+        let precision = self.decimals_x.max(self.decimals_y).max(18);
+        U256::from(10).pow(U256::from(precision - decimals))
+    }
+
+    // invariant D of normalized balances x and y, 0 once either coin is drained
+    fn get_d(&self, x: U256, y: U256) -> U256 {
+        let s = x + y;
+        if x.is_zero() || y.is_zero() {
+            return U256::ZERO;
+        }
+
+        let n = U256::from(N_COINS);
+        let ann = self.ann();
+        let mut d = s;
+        for _ in 0..MAX_ITERATIONS {
+            let d_p = d * d / (x * n) * d / (y * n);
+            let d_prev = d;
+            d = (ann * s + d_p * n) * d / ((ann - U256::from(1)) * d + (n + U256::from(1)) * d_p);
+            if d.abs_diff(d_prev) <= U256::from(1) {
+                break;
+            }
+        }
+
+        d
+    }
+
+    // normalized balance of a coin keeping the invariant d once the other coin's balance is x
+    fn get_y(&self, x: U256, d: U256) -> U256 {
+        let n = U256::from(N_COINS);
+        let ann = self.ann();
+        let c = d * d / (x * n) * d / (ann * n);
+        let b = x + d / ann;
+
+        let mut y = d;
+        for _ in 0..MAX_ITERATIONS {
+            let y_prev = y;
+            y = (y * y + c) / (U256::from(2) * y + b - d);
+            if y.abs_diff(y_prev) <= U256::from(1) {
+                break;
+            }
+        }
+
+        y
+    }
+
+    // price of a whole token x in whole tokens y scaled by 10 ** 18, -dy/dx along the invariant at raw reserves x and y:
+    // (4 * Ann * x^2 * y^2 + D^3 * y) / (4 * Ann * x^2 * y^2 + D^3 * x), divided through by D^3 to stay within 256 bits.
+    // A drained pool has no price, 0
+    fn price(&self, x: U256, y: U256) -> U256 {
+        let (x, y) = (x * self.rate(self.decimals_x), y * self.rate(self.decimals_y));
+        let d = self.get_d(x, y);
+        if d.is_zero() {
+            return U256::ZERO;
+        }
+        let t = x * y / d;
+        let q = U256::from(4) * self.ann() * (t * t / d);
+
+        (q + y) * U256::from(10).pow(U256::from(18)) / (q + x)
+    }
+
+    // executes trade against a pool with raw reserve_in and reserve_out, returns the raw amounts in and out.
+    // NOTE: an exact output trade asking for the whole reserve_out cannot be filled and leaves the pool untouched, as
+    // does any trade against a drained pool
+    fn swap(&self, trade: &dyn ITrade, rates: (U256, U256), reserve_in: &mut U256, reserve_out: &mut U256) -> (U256, U256) {
+        let (rate_in, rate_out) = rates;
+        let (xp_in, xp_out) = (*reserve_in * rate_in, *reserve_out * rate_out);
+        let d = self.get_d(xp_in, xp_out);
+        if d.is_zero() {
+            return (U256::ZERO, U256::ZERO);
+        }
+        let fee = U256::from(self.fee);
+        let denominator = U256::from(FEE_DENOMINATOR);
+
+        let (amount_in, amount_out) = match trade.get_amount_out() {
+            None => {
+                let amount_in = trade.get_amount_in();
+                // rounded down by one unit in favour of the pool, as the pool does
+                let dy = (xp_out - self.get_y(xp_in + amount_in * rate_in, d)).saturating_sub(U256::from(1));
+                let dy = dy - dy * fee / denominator;
+                (amount_in, dy / rate_out)
+            }
+            Some(amount_out) => {
+                // output before the fee, rounded up
+                let dy = (amount_out * rate_out * denominator).div_ceil(denominator - fee);
+                if dy >= xp_out {
+                    return (U256::ZERO, U256::ZERO);
+                }
+                let dx = self.get_y(xp_out - dy, d) - xp_in + U256::from(1);
+                (dx.div_ceil(rate_in), amount_out)
+            }
+        };
+
+        *reserve_in += amount_in;
+        *reserve_out -= amount_out;
+
+        (amount_in, amount_out)
+    }
+
+    // executes trade against reserves x and y in the direction of the trade, returns the amounts in and out
+    fn step(&self, trade: &dyn ITrade, x: &mut U256, y: &mut U256) -> (U256, U256) {
+        let (rate_x, rate_y) = (self.rate(self.decimals_x), self.rate(self.decimals_y));
+        match trade.get_direction() {
+            TradeDirection::Sell => self.swap(trade, (rate_x, rate_y), x, y),
+            TradeDirection::Buy => self.swap(trade, (rate_y, rate_x), y, x),
+        }
+    }
+
+    // reserves (X, Y) after the first i trades of o are executed, and the amounts in and out of the i'th trade
+    fn execute(&self, o: &dyn Trades, i: usize) -> ((U256, U256), (U256, U256)) {
+        let mut x = self.reserve_x;
+        let mut y = self.reserve_y;
+        let mut amounts = (U256::ZERO, U256::ZERO);
+
+        for t in 1..i + 1 {
+            amounts = self.step(o.trade(t), &mut x, &mut y);
+        }
+
+        ((x, y), amounts)
+    }
+}
+
+impl Model for StableSwapModel {
+    fn y_out(&self, o: &dyn Trades, i: usize) -> U256 {
+        if o.trade(i).get_direction() == TradeDirection::Sell {
+            return self.execute(o, i).1 .1;
+        }
+
+        U256::from(0)
+    }
+
+    fn x_out(&self, o: &dyn Trades, i: usize) -> U256 {
+        if o.trade(i).get_direction() == TradeDirection::Buy {
+            return self.execute(o, i).1 .1;
+        }
+
+        U256::from(0)
+    }
+
+    fn amount_in(&self, o: &dyn Trades, i: usize) -> U256 {
+        self.execute(o, i).1 .0
+    }
+
+    fn P(&self, o: &dyn Trades, i: usize) -> U256 {
+        let (x, y) = self.execute(o, i).0;
+        self.price(x, y)
+    }
+
+    fn after(&self, o: &dyn Trades, i: usize) -> Box<dyn Model> {
+        let (reserve_x, reserve_y) = self.execute(o, i).0;
+        Box::new(StableSwapModel {
+            reserve_x,
+            reserve_y,
+            ..*self
+        })
+    }
+
+    fn simulate(&self, trade: &dyn ITrade) -> (U256, U256, U256) {
+        let (mut x, mut y) = (self.reserve_x, self.reserve_y);
+        let (amount_in, amount_out) = self.step(trade, &mut x, &mut y);
+        (amount_in, amount_out, self.price(x, y))
+    }
+
+    fn decimals(&self) -> (u8, u8) {
+        (self.decimals_x, self.decimals_y)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::stable_swap_model::StableSwapModel;
+use crate::clvr::model::{Model, Omega};
+use crate::trades::implementation::Trade;
+use crate::trades::TradeDirection;
+use alloy::primitives::{aliases::U24, U256};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    fn usd(x: u128) -> U256 {
+        U256::from(x) * U256::from(1_000_000)
+    }
+
+    #[test]
+    fn test_stable_swap_price() {
+        let empty: Omega<Trade> = Omega::new();
+
+        // a balanced pool trades at par whatever A
+        let model = StableSwapModel::new(size(1000), size(1000), 100, U24::ZERO);
+        assert_eq!(model.P(&empty, 0), size(1));
+        let model = StableSwapModel::new(size(1000), size(1000), 1, U24::ZERO);
+        assert_eq!(model.P(&empty, 0), size(1));
+
+        // x is cheaper once abundant, far less so than on the constant product curve
+        let stable = StableSwapModel::new(size(1500), size(500), 100, U24::ZERO);
+        let constant_product = CLVRModel::new(size(1500), size(500));
+        assert!(stable.P(&empty, 0) < size(1));
+        assert!(stable.P(&empty, 0) > constant_product.P(&empty, 0));
+    }
+
+    #[test]
+    fn test_stable_swap_exact_input() {
+        let model = StableSwapModel::new(size(1000), size(1000), 100, U24::ZERO);
+        let constant_product = CLVRModel::new(size(1000), size(1000));
+        let sell = Trade::new(size(100), TradeDirection::Sell);
+
+        // close to 1:1, better than x * y = k
+        let (amount_in, amount_out, price) = model.simulate(&sell);
+        assert_eq!(amount_in, size(100));
+        assert!(amount_out < size(100) && amount_out > size(99));
+        assert!(amount_out > constant_product.simulate(&sell).1);
+        assert!(price < size(1));
+
+        // the fee is taken from the output
+        let fee = StableSwapModel::new(size(1000), size(1000), 100, U24::from(100));
+        let with_fee = fee.simulate(&sell).1;
+        assert_eq!(with_fee, amount_out - amount_out / U256::from(10_000));
+
+        // the model after a trade is the pool once it is executed
+        let omega = Omega::new_from(vec![sell]);
+        assert_eq!(model.after(&omega, 1).P(&Omega::<Trade>::new(), 0), price);
+        assert_eq!(model.y_out(&omega, 1), amount_out);
+    }
+
+    #[test]
+    fn test_stable_swap_exact_output() {
+        let model = StableSwapModel::new(usd(1_000_000), usd(1_000_000), 200, U24::from(100)).with_decimals(6, 6);
+
+        // the input buys at least the output asked for, and one unit less does not
+        let buy = Trade::new_exact_output(usd(1000), usd(1100), TradeDirection::Buy);
+        let (amount_in, amount_out, _) = model.simulate(&buy);
+        assert_eq!(amount_out, usd(1000));
+        assert!(model.simulate(&Trade::new(amount_in, TradeDirection::Buy)).1 >= usd(1000));
+        assert!(model.simulate(&Trade::new(amount_in - U256::from(1), TradeDirection::Buy)).1 < usd(1000));
+
+        // the whole reserve cannot be bought
+        let all = Trade::new_exact_output(usd(1_000_000), usd(2_000_000), TradeDirection::Buy);
+        assert_eq!(model.simulate(&all).1, U256::ZERO);
+    }
+    #[test]
+    fn test_stable_swap_edge_cases() {
+        let sell = Trade::new(size(1), TradeDirection::Sell);
+
+        // a drained pool neither trades nor has a price
+        let drained = StableSwapModel::new(size(1000), U256::ZERO, 100, U24::ZERO);
+        assert_eq!(drained.simulate(&sell), (U256::ZERO, U256::ZERO, U256::ZERO));
+        let empty = StableSwapModel::new(U256::ZERO, U256::ZERO, 100, U24::ZERO);
+        assert_eq!(empty.simulate(&Trade::new_exact_output(size(1), size(2), TradeDirection::Buy)).1, U256::ZERO);
+
+        // A is at least 1
+        let flat = StableSwapModel::new(size(1000), size(1000), 0, U24::ZERO);
+        assert_eq!(flat.simulate(&sell), StableSwapModel::new(size(1000), size(1000), 1, U24::ZERO).simulate(&sell));
+
+        // a coin with more than 18 decimals trades as one with 18
+        let wide = |x: u128| size(x) * U256::from(1_000_000);
+        let model = StableSwapModel::new(wide(1000), size(1000), 100, U24::ZERO).with_decimals(24, 18);
+        let reference = StableSwapModel::new(size(1000), size(1000), 100, U24::ZERO);
+        let (_, amount_out, price) = model.simulate(&Trade::new(wide(100), TradeDirection::Sell));
+        assert_eq!(amount_out, reference.simulate(&Trade::new(size(100), TradeDirection::Sell)).1);
+        assert_eq!(price, reference.simulate(&Trade::new(size(100), TradeDirection::Sell)).2);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::clvr::model::{Model, Trades};
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, I256, U160, U256};
+use uniswap_v3_sdk::prelude::{
+    add_delta, compute_swap_step, get_sqrt_ratio_at_tick, get_tick_at_sqrt_ratio, mul_div, Tick,
+    TickIndex, MAX_SQRT_RATIO, MIN_SQRT_RATIO, Q96,
+};
+
+// State of a V3 pool between two swaps
+#[derive(Clone, Copy)]
+struct PoolState {
+    sqrt_price_x96: U160,
+    liquidity: u128, // in-range liquidity
+    tick: i32,
+}
+
+// Model of a Uniswap V3 pool with concentrated liquidity.
+// NOTE: token x is the pool's token0 and token y its token1, so a Sell is a zeroForOne swap.
+// X and Y are the virtual reserves of the active range: X = L / sqrt(P) and Y = L * sqrt(P)
+pub struct V3Model {
+    initial: PoolState,
+    fee: U24,
+    ticks: Vec<Tick>, // initialized ticks sorted by index
+    decimals_x: u8,
+    decimals_y: u8,
+}
+
+impl V3Model {
+    pub fn new(sqrt_price_x96: U160, liquidity: u128, tick: i32, fee: U24, mut ticks: Vec<Tick>) -> Self {
+        ticks.sort_by_key(|t| t.index);
+
+        V3Model {
+            initial: PoolState {
+                sqrt_price_x96,
+                liquidity,
+                tick,
+            },
+            fee,
+            ticks,
+            decimals_x: 18,
+            decimals_y: 18,
+        }
+    }
+
+    pub fn with_decimals(mut self, decimals_x: u8, decimals_y: u8) -> Self {
+        self.decimals_x = decimals_x;
+        self.decimals_y = decimals_y;
+        self
+    }
+
+    // next initialized tick crossed when moving the price from tick in the direction of the swap
+    fn next_tick(&self, tick: i32, zero_for_one: bool) -> Option<&Tick> {
+        if zero_for_one {
+            self.ticks.iter().rev().find(|t| t.index <= tick)
+        } else {
+            self.ticks.iter().find(|t| t.index > tick)
+        }
+    }
+
+    fn limit(zero_for_one: bool, sqrt_price_limit_x96: Option<U160>) -> U160 {
+        match sqrt_price_limit_x96 {
+            Some(limit) => limit,
+            None if zero_for_one => MIN_SQRT_RATIO + U160::from(1),
+            None => MAX_SQRT_RATIO - U160::from(1),
+        }
+    }
+
+    // a limit on the wrong side of the current price makes the pool revert (SPL)
+    fn rejects(state: &PoolState, zero_for_one: bool, limit: U160) -> bool {
+        (zero_for_one && limit >= state.sqrt_price_x96) || (!zero_for_one && limit <= state.sqrt_price_x96)
+    }
+
+    // executes a swap against state, amount is positive for exact input and negative for exact output swaps.
+    // The swap stops, partially filled, once the price reaches sqrt_price_limit_x96. Returns the amounts in and out,
+    // nothing is filled if the pool rejects the swap (see Model::reverts)
+    fn swap(&self, state: &mut PoolState, zero_for_one: bool, amount: I256, sqrt_price_limit_x96: Option<U160>) -> (U256, U256) {
+        let limit = Self::limit(zero_for_one, sqrt_price_limit_x96);
+        if Self::rejects(state, zero_for_one, limit) {
+            return (U256::ZERO, U256::ZERO);
+        }
+
+        let exact_input = amount > I256::ZERO;
+        let mut remaining = amount;
+        let mut amount_in = U256::ZERO;
+        let mut amount_out = U256::ZERO;
+
+        while remaining != I256::ZERO && state.sqrt_price_x96 != limit {
+            let next = self.next_tick(state.tick, zero_for_one);
+            let tick_sqrt_price = next.map(|t| get_sqrt_ratio_at_tick(t.index.to_i24()).expect("Invalid tick"));
+            let target = match tick_sqrt_price {
+                Some(p) if zero_for_one => p.max(limit),
+                Some(p) => p.min(limit),
+                None => limit,
+            };
+
+            let (sqrt_price_next, step_in, step_out, step_fee) =
+                compute_swap_step(state.sqrt_price_x96, target, state.liquidity, remaining, self.fee)
+                    .expect("Failed to compute swap step");
+
+            if exact_input {
+                remaining -= I256::from_raw(step_in + step_fee);
+            } else {
+                remaining += I256::from_raw(step_out);
+            }
+            amount_in += step_in + step_fee;
+            amount_out += step_out;
+            state.sqrt_price_x96 = sqrt_price_next;
+
+            match next {
+                Some(t) if Some(sqrt_price_next) == tick_sqrt_price => {
+                    // crossing the tick adds or removes the liquidity of the ranges starting or ending there
+                    let liquidity_net = if zero_for_one { -t.liquidity_net } else { t.liquidity_net };
+                    state.liquidity = add_delta(state.liquidity, liquidity_net).expect("Liquidity overflow");
+                    state.tick = if zero_for_one { t.index - 1 } else { t.index };
+                }
+                _ => {
+                    state.tick = get_tick_at_sqrt_ratio(sqrt_price_next).expect("Invalid sqrt price").as_i32();
+                }
+            }
+        }
+
+        (amount_in, amount_out)
+    }
+
+    // executes trade against state, returns the amounts in and out
+    fn step(&self, state: &mut PoolState, trade: &dyn ITrade) -> (U256, U256) {
+        let zero_for_one = trade.get_direction() == TradeDirection::Sell;
+        let amount = match trade.get_amount_out() {
+            None => I256::from_raw(trade.get_amount_in()),
+            Some(amount_out) => -I256::from_raw(amount_out),
+        };
+
+        self.swap(state, zero_for_one, amount, trade.get_sqrt_price_limit_x96())
+    }
+
+    // pool state after the first i trades of o are executed, together with the amounts in and out of the i'th trade
+    fn state(&self, o: &dyn Trades, i: usize) -> (PoolState, (U256, U256)) {
+        let mut state = self.initial;
+        let mut amounts = (U256::ZERO, U256::ZERO);
+
+        for t in 1..i + 1 {
+            amounts = self.step(&mut state, o.trade(t));
+        }
+
+        (state, amounts)
+    }
+}
+
+// price of a whole token0 in whole tokens1 scaled by 10 ** 18 (see Model::P), from a pool's sqrtPriceX96.
+// The raw price is (sqrtPriceX96 / 2^96)^2, with 512 bit intermediates since sqrtPriceX96^2 may not fit in U256
+pub fn sqrt_price_to_price(sqrt_price_x96: U160, decimals: (u8, u8)) -> U256 {
+    let ten = U256::from(10);
+    let sqrt_price = U256::from(sqrt_price_x96);
+
+    let price_x96 = mul_div(sqrt_price, sqrt_price, Q96).expect("P overflows");
+    let scale_x = ten.pow(U256::from(18 + decimals.0 as u64));
+    let scale_y = ten.pow(U256::from(decimals.1));
+    mul_div(price_x96, scale_x, Q96 * scale_y).expect("P overflows")
+}
+
+impl Model for V3Model {
+    fn y_out(&self, o: &dyn Trades, i: usize) -> U256 {
+        if o.trade(i).get_direction() == TradeDirection::Sell {
+            return self.state(o, i).1 .1;
+        }
+
+        U256::from(0)
+    }
+
+    fn x_out(&self, o: &dyn Trades, i: usize) -> U256 {
+        if o.trade(i).get_direction() == TradeDirection::Buy {
+            return self.state(o, i).1 .1;
+        }
+
+        U256::from(0)
+    }
+
+    fn amount_in(&self, o: &dyn Trades, i: usize) -> U256 {
+        self.state(o, i).1 .0
+    }
+
+    fn P(&self, o: &dyn Trades, i: usize) -> U256 {
+        let (state, _) = self.state(o, i);
+        sqrt_price_to_price(state.sqrt_price_x96, self.decimals())
+    }
+
+    fn after(&self, o: &dyn Trades, i: usize) -> Box<dyn Model> {
+        let (state, _) = self.state(o, i);
+        Box::new(V3Model {
+            initial: state,
+            fee: self.fee,
+            ticks: self.ticks.clone(),
+            decimals_x: self.decimals_x,
+            decimals_y: self.decimals_y,
+        })
+    }
+
+    fn simulate(&self, trade: &dyn ITrade) -> (U256, U256, U256) {
+        let mut state = self.initial;
+        let (amount_in, amount_out) = self.step(&mut state, trade);
+        (amount_in, amount_out, sqrt_price_to_price(state.sqrt_price_x96, self.decimals()))
+    }
+
+    fn reverts(&self, o: &dyn Trades, i: usize) -> bool {
+        let (state, _) = self.state(o, i - 1);
+        let trade = o.trade(i);
+        let zero_for_one = trade.get_direction() == TradeDirection::Sell;
+        Self::rejects(&state, zero_for_one, Self::limit(zero_for_one, trade.get_sqrt_price_limit_x96()))
+    }
+
+    fn decimals(&self) -> (u8, u8) {
+        (self.decimals_x, self.decimals_y)
+    }
+}
@@ -0,0 +1,117 @@
+use crate::clvr::metrics::quote;
+use crate::clvr::model::v3_model::V3Model;
+use crate::clvr::model::{Model, Omega};
+use crate::clvr::slippage::reverts;
+use crate::trades::implementation::Trade;
+use crate::trades::TradeDirection;
+use alloy::primitives::{aliases::U24, U160, U256};
+use uniswap_v3_sdk::prelude::Tick;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_RANGE_TICK: i32 = 887220;
+    const LIQUIDITY: u128 = 1_000_000_000_000_000_000;
+
+    fn to_f64(x: U256) -> f64 {
+        x.to_string().parse().unwrap()
+    }
+
+    fn position(tick_lower: i32, tick_upper: i32, liquidity: u128) -> Vec<Tick> {
+        vec![
+            Tick::new(tick_lower, liquidity, liquidity as i128),
+            Tick::new(tick_upper, liquidity, -(liquidity as i128)),
+        ]
+    }
+
+    // price 1, sqrtPriceX96 = 2^96
+    fn model(ticks: Vec<Tick>, liquidity: u128) -> V3Model {
+        V3Model::new(U160::from(1) << 96, liquidity, 0, U24::from(0), ticks)
+    }
+
+    #[test]
+    fn test_v3_full_range_matches_constant_product() {
+        let model = model(position(-FULL_RANGE_TICK, FULL_RANGE_TICK, LIQUIDITY), LIQUIDITY);
+
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(U256::from(LIQUIDITY / 10), TradeDirection::Sell)));
+        omega.push(Box::new(Trade::new(U256::from(LIQUIDITY / 4), TradeDirection::Buy)));
+
+        // with a single full range position the virtual reserves L / sqrt(P) and L * sqrt(P) behave as x * y = k
+        let (x, y) = (LIQUIDITY as f64, LIQUIDITY as f64);
+        let sell = LIQUIDITY as f64 / 10.0;
+        let expected_y_out = y * sell / (x + sell);
+        let (x, y) = (x + sell, y - expected_y_out);
+        let buy = LIQUIDITY as f64 / 4.0;
+        let expected_x_out = x * buy / (y + buy);
+
+        let y_out = to_f64(model.y_out(&omega, 1));
+        let x_out = to_f64(model.x_out(&omega, 2));
+        assert!((y_out - expected_y_out).abs() / expected_y_out < 1e-9);
+        assert!((x_out - expected_x_out).abs() / expected_x_out < 1e-9);
+    }
+
+    #[test]
+    fn test_v3_crosses_initialized_tick() {
+        // full range liquidity plus a second position active only between ticks -600 and 600
+        let mut ticks = position(-FULL_RANGE_TICK, FULL_RANGE_TICK, LIQUIDITY);
+        ticks.extend(position(-600, 600, LIQUIDITY));
+        let model = model(ticks, 2 * LIQUIDITY);
+
+        // large enough to push the price below tick -600
+        let amount_in = LIQUIDITY / 5;
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(U256::from(amount_in), TradeDirection::Sell)));
+
+        // token0 in moves 1 / sqrt(P) by amount / L, first with both positions in range, then with the full range only
+        let sqrt_price_at_boundary = 1.0001f64.powi(-600).sqrt();
+        let amount_to_boundary = 2.0 * LIQUIDITY as f64 * (1.0 / sqrt_price_at_boundary - 1.0);
+        let remaining = amount_in as f64 - amount_to_boundary;
+        let inverse_sqrt_price = 1.0 / sqrt_price_at_boundary + remaining / LIQUIDITY as f64;
+        let expected_price = 1.0 / (inverse_sqrt_price * inverse_sqrt_price);
+
+        assert!(remaining > 0.0);
+        let price = to_f64(model.P(&omega, 1)) / 1e18;
+        assert!((price - expected_price).abs() / expected_price < 1e-9);
+    }
+
+    #[test]
+    fn test_v3_price_limit_partial_fill() {
+        let model = model(position(-FULL_RANGE_TICK, FULL_RANGE_TICK, LIQUIDITY), LIQUIDITY);
+
+        // token0 in moves 1 / sqrt(P) by amount / L, so reaching sqrt price 0.9 takes L * (1 / 0.9 - 1)
+        let limit = (U160::from(9) << 96) / U160::from(10);
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(U256::from(LIQUIDITY), TradeDirection::Sell).with_sqrt_price_limit_x96(limit)));
+
+        let fill = quote(&model, &omega, 1);
+        let expected_amount_in = LIQUIDITY as f64 * (1.0 / 0.9 - 1.0);
+        assert!(fill.partial);
+        assert!((to_f64(fill.amount_in) - expected_amount_in).abs() / expected_amount_in < 1e-9);
+
+        let price = to_f64(model.P(&omega, 1)) / 1e18;
+        assert!((price - 0.81).abs() / 0.81 < 1e-9);
+    }
+
+    #[test]
+    fn test_v3_wrong_side_price_limit_reverts() {
+        let model = model(position(-FULL_RANGE_TICK, FULL_RANGE_TICK, LIQUIDITY), LIQUIDITY);
+
+        // selling token0 lowers the price, a limit above the current price is on the wrong side
+        let above = (U160::from(11) << 96) / U160::from(10);
+        let mut omega = Omega::new();
+        omega.push(Box::new(Trade::new(U256::from(LIQUIDITY / 10), TradeDirection::Sell).with_sqrt_price_limit_x96(above)));
+        omega.push(Box::new(
+            Trade::new_exact_output(U256::from(LIQUIDITY / 10), U256::from(LIQUIDITY), TradeDirection::Sell)
+                .with_sqrt_price_limit_x96(above),
+        ));
+        omega.push(Box::new(Trade::new(U256::from(LIQUIDITY / 10), TradeDirection::Buy).with_sqrt_price_limit_x96(above)));
+
+        // an exact input trade without minimum and an exact output trade with a limit still revert
+        assert!(model.reverts(&omega, 1));
+        assert!(reverts(&model, &omega, 1));
+        assert!(reverts(&model, &omega, 2));
+        assert!(!reverts(&model, &omega, 3));
+    }
+}
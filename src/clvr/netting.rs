@@ -0,0 +1,91 @@
+use crate::clvr::model::{Omega, TradeId};
+use crate::trades::implementation::Trade;
+use crate::trades::TradeDirection;
+use alloy::primitives::U256;
+use serde::Serialize;
+
+// Fill of a single trade split between the internal match and the pool
+#[derive(Debug, Serialize)]
+pub struct Fill {
+    pub id: TradeId,       // identity of the trade in the batch, which keeps it in the residual
+    pub matched_in: U256,  // amount in matched against opposite trades of the batch
+    pub matched_out: U256, // amount out received from the match, at the pool's spot price
+    pub pool_in: U256,     // residual amount in sent to the pool
+}
+
+// Matches Buy and Sell trades of the batch against each other at price, the spot price of the pool, so that the match
+// and the residual swaps clear at a consistent price. The larger side is filled pro rata.
+// omega is left with the residual trades to execute against the pool: partially matched trades are replaced in place
+// by their remainder and fully matched ones removed, so every residual trade keeps its identity. Returns the fill of
+// every trade in the order of omega, none if price is zero as nothing can be matched at it.
+// NOTE: only exact input trades are matched, exact output trades are sent to the pool untouched
+pub fn net(price: U256, decimals: (u8, u8), omega: &mut Omega) -> Vec<Fill> {
+    if price.is_zero() {
+        return Vec::new();
+    }
+
+    // price is per whole token, so raw amounts of x are rescaled to raw amounts of y
+    let ten = U256::from(10);
+    let scale_x = ten.pow(U256::from(18 + decimals.0 as u64));
+    let scale_y = ten.pow(U256::from(decimals.1));
+    let to_y = |amount_x: U256| amount_x * price * scale_y / scale_x;
+    let to_x = |amount_y: U256| amount_y * scale_x / (price * scale_y);
+
+    let nettable = |t: usize| omega[t].get_amount_out().is_none();
+    let volume = |direction: TradeDirection| -> U256 {
+        (1..omega.len() + 1)
+            .filter(|&t| nettable(t) && omega[t].get_direction() == direction)
+            .map(|t| omega[t].get_amount_in())
+            .sum()
+    };
+    let sold_x = volume(TradeDirection::Sell);
+    let bought_y = volume(TradeDirection::Buy);
+    let matched_x = sold_x.min(to_x(bought_y));
+    let matched_y = to_y(matched_x);
+
+    let fills: Vec<Fill> = (1..omega.len() + 1)
+        .map(|t| {
+            let amount_in = omega[t].get_amount_in();
+            let (matched_in, matched_out) = match omega[t].get_direction() {
+                _ if !nettable(t) => (U256::ZERO, U256::ZERO),
+                TradeDirection::Sell if !sold_x.is_zero() => {
+                    let matched_in = amount_in * matched_x / sold_x;
+                    (matched_in, to_y(matched_in))
+                }
+                TradeDirection::Buy if !bought_y.is_zero() => {
+                    let matched_in = amount_in * matched_y / bought_y;
+                    (matched_in, to_x(matched_in))
+                }
+                _ => (U256::ZERO, U256::ZERO),
+            };
+
+            Fill {
+                id: omega.id(t),
+                matched_in,
+                matched_out,
+                pool_in: amount_in - matched_in,
+            }
+        })
+        .collect();
+
+    // unmatched trades stay as they are, partially matched ones are replaced by their remainder
+    for (trade, fill) in omega.iter_mut().zip(&fills) {
+        if fill.matched_in.is_zero() || fill.pool_in.is_zero() {
+            continue;
+        }
+        // the minimum out of the remainder is what the match did not already deliver
+        let mut remainder = Trade::new(fill.pool_in, trade.get_direction())
+            .with_fee(trade.get_fee())
+            .with_pool(trade.get_pool())
+            .with_amount_out_minimum(trade.get_amount_out_minimum().saturating_sub(fill.matched_out));
+        if let Some(limit) = trade.get_sqrt_price_limit_x96() {
+            remainder = remainder.with_sqrt_price_limit_x96(limit);
+        }
+        *trade = Box::new(remainder);
+    }
+    for fill in fills.iter().filter(|fill| !fill.matched_in.is_zero() && fill.pool_in.is_zero()) {
+        omega.remove_id(fill.id);
+    }
+
+    fills
+}
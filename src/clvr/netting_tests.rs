@@ -0,0 +1,60 @@
+use crate::clvr::model::{Omega, TradeId};
+use crate::clvr::netting::net;
+use crate::trades::implementation::Trade;
+use crate::trades::TradeDirection;
+use alloy::primitives::U256;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    #[test]
+    fn test_netting() {
+        let mut omega: Omega = Omega::new();
+        omega.push(Box::new(Trade::new(size(6), TradeDirection::Sell).with_amount_out_minimum(size(5))));
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Buy)));
+        omega.push(Box::new(Trade::new(size(2), TradeDirection::Sell)));
+        omega.push(Box::new(Trade::new_exact_output(size(1), size(3), TradeDirection::Sell)));
+
+        // at a price of 2 the 10 y bought buy 5 of the 8 x sold: the buy is filled entirely, the sells pro rata
+        let price = size(2);
+        let ids: Vec<TradeId> = omega.ids().collect();
+        let fills = net(price, (18, 18), &mut omega);
+        let residual = omega;
+        let hundredths = |x: u128| size(x) / U256::from(100);
+
+        assert_eq!(fills.len(), 4);
+        assert_eq!(fills[0].matched_in, hundredths(375));
+        assert_eq!(fills[0].matched_out, hundredths(750));
+        assert_eq!(fills[1].matched_in, size(10));
+        assert_eq!(fills[1].matched_out, size(5));
+        assert_eq!(fills[1].pool_in, U256::ZERO);
+        assert_eq!(fills[2].matched_in, hundredths(125));
+        assert_eq!(fills[3].matched_in, U256::ZERO);
+
+        // the remainders of the sells and the untouched exact output trade go to the pool
+        assert_eq!(residual.len(), 3);
+        assert_eq!(residual[1].get_amount_in(), hundredths(225));
+        assert_eq!(residual[1].get_amount_out_minimum(), U256::ZERO); // the match already delivered the minimum
+        assert_eq!(residual[2].get_amount_in(), hundredths(75));
+        assert_eq!(residual[3].get_amount_out(), Some(size(1)));
+
+        // every trade keeps its identity, the fully matched buy only through its fill
+        assert_eq!(fills.iter().map(|fill| fill.id).collect::<Vec<_>>(), ids);
+        assert_eq!(residual.ids().collect::<Vec<_>>(), vec![ids[0], ids[2], ids[3]]);
+
+        // a pool without a price nets nothing
+        let mut omega: Omega = Omega::new();
+        omega.push(Box::new(Trade::new(size(6), TradeDirection::Sell)));
+        omega.push(Box::new(Trade::new(size(10), TradeDirection::Buy)));
+        assert!(net(U256::ZERO, (18, 18), &mut omega).is_empty());
+        assert_eq!(omega.len(), 2);
+    }
+}
@@ -0,0 +1,233 @@
+use alloy::primitives::{Sign, I256, U256, U512};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// Numbers the ordering objective is computed in. By default a native fixed point number, with the rug feature a
+// 256 bit rug::Float (GMP/MPFR). Both provide the same operations on logs of prices
+#[cfg(not(feature = "rug"))]
+pub type Real = Fixed;
+#[cfg(feature = "rug")]
+pub type Real = rug::Float;
+
+#[cfg(not(feature = "rug"))]
+pub fn zero() -> Real {
+    Fixed::ZERO
+}
+
+#[cfg(feature = "rug")]
+pub fn zero() -> Real {
+    rug::Float::with_val(256, 0)
+}
+
+#[cfg(not(feature = "rug"))]
+pub fn ln(x: U256) -> Real {
+    ln_fixed(x)
+}
+
+#[cfg(feature = "rug")]
+pub fn ln(x: U256) -> Real {
+    ln_float(x)
+}
+
+const FRACTION_BITS: usize = 128;
+const ONE: U256 = U256::from_limbs([0, 0, 1, 0]);
+const SQRT_2: U256 = U256::from_limbs([0xb2fb1366ea957d3e, 0x6a09e667f3bcc908, 1, 0]); // sqrt(2) * 2 ** 128
+const LN_2: U256 = U256::from_limbs([0xc9e3b39803f2f6af, 0xb17217f7d1cf79ab, 0, 0]); // ln(2) * 2 ** 128
+const LN_10: U256 = U256::from_limbs([0xa95b58ae0b4c28a3, 0x4d763776aaa2b05b, 2, 0]); // ln(10) * 2 ** 128
+
+// Signed fixed point number with 128 fractional bits
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fixed(I256);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(I256::ZERO);
+
+    fn from_sign_and_abs(sign: Sign, abs: U256) -> Self {
+        Fixed(I256::checked_from_sign_and_abs(sign, abs).expect("Fixed point overflow"))
+    }
+
+    // exact, as every finite f64 below 2 ** 127 is a multiple of 2 ** -1074 and only the bits below 2 ** -128 are cut
+    pub fn from_f64(x: f64) -> Self {
+        assert!(x.is_finite() && x.abs() < 2f64.powi(127), "{} is out of the fixed point range", x);
+        let bits = x.abs().to_bits();
+        let (mantissa, exponent) = match (bits >> 52) as i64 {
+            0 => (bits, -1074),
+            biased => ((bits & ((1 << 52) - 1)) | (1 << 52), biased - 1075),
+        };
+        let shift = exponent + FRACTION_BITS as i64;
+        let abs = if shift >= 0 {
+            U256::from(mantissa) << shift as usize
+        } else {
+            U256::from(mantissa).wrapping_shr((-shift) as usize)
+        };
+
+        Fixed::from_sign_and_abs(if x < 0.0 { Sign::Negative } else { Sign::Positive }, abs)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let (sign, abs) = self.0.into_sign_and_abs();
+        let x = f64::from(abs) / 2f64.powi(FRACTION_BITS as i32);
+        if sign.is_negative() {
+            -x
+        } else {
+            x
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        Fixed(self.0.abs())
+    }
+
+    pub fn square(self) -> Self {
+        self.clone() * self
+    }
+
+    pub fn max(self, other: &Self) -> Self {
+        Ord::max(self, other.clone())
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Fixed) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl Sub<&Fixed> for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, rhs: &Fixed) -> Fixed {
+        Fixed(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Fixed) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(-self.0)
+    }
+}
+
+// rounds toward zero
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, rhs: Fixed) -> Fixed {
+        let (sign, abs) = self.0.into_sign_and_abs();
+        let (rhs_sign, rhs_abs) = rhs.0.into_sign_and_abs();
+        let sign = if sign == rhs_sign { Sign::Positive } else { Sign::Negative };
+        Fixed::from_sign_and_abs(sign, mul(abs, rhs_abs))
+    }
+}
+
+impl Mul<f64> for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, rhs: f64) -> Fixed {
+        self * Fixed::from_f64(rhs)
+    }
+}
+
+// product of two unsigned numbers with 128 fractional bits
+fn mul(a: U256, b: U256) -> U256 {
+    U256::from((U512::from(a) * U512::from(b)) >> FRACTION_BITS)
+}
+
+// quotient of two unsigned numbers with 128 fractional bits
+fn div(a: U256, b: U256) -> U256 {
+    U256::from((U512::from(a) << FRACTION_BITS) / U512::from(b))
+}
+
+// atanh(s) = s + s^3 / 3 + s^5 / 5 + ..., for 0 <= s < 1 with 128 fractional bits
+fn atanh(s: U256) -> U256 {
+    let s_squared = mul(s, s);
+    let mut power = s;
+    let mut sum = U256::ZERO;
+    let mut k = 1u64;
+
+    // the terms shrink by a factor of at least 30 for the s of ln_fixed, so this takes about 25 iterations
+    while !power.is_zero() {
+        sum += power / U256::from(k);
+        power = mul(power, s_squared);
+        k += 2;
+    }
+
+    sum
+}
+
+// NOTE: x is a price scaled by 10 ** 18 (see Model::P), so this computes the natural log of x / 10 ** 18.
+// P only rounds down to 0 below 10 ** -18, which is treated as 10 ** -18.
+// Accurate to about 2 ** -118, far below any difference between the objectives of two orderings
+pub fn ln_fixed(x: U256) -> Fixed {
+    let x = x.max(U256::from(1));
+
+    // x = 2 ** n * m with m in [1 / sqrt(2), sqrt(2))
+    let mut n = x.bit_len() - 1;
+    let mut m = if n <= FRACTION_BITS {
+        x << (FRACTION_BITS - n)
+    } else {
+        x >> (n - FRACTION_BITS)
+    };
+    if m >= SQRT_2 {
+        m >>= 1;
+        n += 1;
+    }
+
+    // ln(m) = 2 * atanh(s) with s = (m - 1) / (m + 1), |s| < 0.172
+    let ln_m = if m >= ONE {
+        Fixed::from_sign_and_abs(Sign::Positive, atanh(div(m - ONE, m + ONE)) << 1)
+    } else {
+        Fixed::from_sign_and_abs(Sign::Negative, atanh(div(ONE - m, m + ONE)) << 1)
+    };
+
+    ln_m + Fixed::from_sign_and_abs(Sign::Positive, LN_2 * U256::from(n))
+        - Fixed::from_sign_and_abs(Sign::Positive, LN_10 * U256::from(18))
+}
+
+// natural log of x / 10 ** 18 computed by MPFR
+#[cfg(feature = "rug")]
+pub fn ln_float(x: U256) -> rug::Float {
+    use rug::ops::Pow;
+    use rug::{Float, Integer};
+
+    let x_int = Integer::from_str_radix(&x.to_string(), 10).expect("Failed to convert U256 to Integer");
+    let x_float: Float = Float::with_val(256, &x_int) / Float::with_val(256, 10).pow(18);
+
+    x_float.ln()
+}
+
+#[cfg(feature = "rug")]
+impl From<Fixed> for rug::Float {
+    fn from(x: Fixed) -> rug::Float {
+        let (sign, abs) = x.0.into_sign_and_abs();
+        let abs = rug::Integer::from_str_radix(&abs.to_string(), 10).expect("Failed to convert U256 to Integer");
+        let x = rug::Float::with_val(256, abs) >> FRACTION_BITS as u32;
+        if sign.is_negative() {
+            -x
+        } else {
+            x
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::clvr::real::{ln_fixed, Fixed};
+use alloy::primitives::U256;
+#[cfg(feature = "rug")]
+use crate::clvr::algorithm::{clvr_order, Weights};
+#[cfg(feature = "rug")]
+use crate::clvr::model::clvr_model::CLVRModel;
+#[cfg(feature = "rug")]
+use crate::clvr::model::{Model, Omega};
+#[cfg(feature = "rug")]
+use crate::clvr::real::ln_float;
+#[cfg(feature = "rug")]
+use crate::trades::implementation::Trade;
+#[cfg(feature = "rug")]
+use crate::trades::TradeDirection;
+#[cfg(feature = "rug")]
+use rand::rngs::StdRng;
+#[cfg(feature = "rug")]
+use rand::{Rng, SeedableRng};
+#[cfg(feature = "rug")]
+use rug::Float;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    #[test]
+    fn test_ln_fixed() {
+        assert!(ln_fixed(size(1)).abs() < Fixed::from_f64(1e-30));
+        for x in [1u128, 2, 3, 10, 1_000, 123_456_789] {
+            assert!((ln_fixed(size(x)).to_f64() - (x as f64).ln()).abs() < 1e-12);
+        }
+
+        // the smallest price, 10 ** -18, and one far above any pool price
+        assert!((ln_fixed(U256::from(1)).to_f64() + 18.0 * 10f64.ln()).abs() < 1e-12);
+        assert!((ln_fixed(U256::MAX).to_f64() - (256.0 * 2f64.ln() - 18.0 * 10f64.ln())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_fixed_arithmetic() {
+        let a = Fixed::from_f64(-1.5);
+        let b = Fixed::from_f64(0.25);
+        assert_eq!((a.clone() * b.clone()).to_f64(), -0.375);
+        assert_eq!((a.clone() - b.clone()).square().to_f64(), 3.0625);
+        assert_eq!(a.clone().abs().max(&b).to_f64(), 1.5);
+        assert_eq!((-a.clone() + b.clone() * 2.0).to_f64(), 2.0);
+    }
+
+    #[cfg(feature = "rug")]
+    #[test]
+    fn test_ln_fixed_matches_rug() {
+        let mut rng = StdRng::seed_from_u64(43);
+        for _ in 0..1000 {
+            // prices of every magnitude, from 10 ** -18 to 2 ** 256 / 10 ** 18
+            let bits: usize = rng.gen_range(1..257);
+            let x = U256::from_limbs(rng.gen()) >> (256 - bits);
+            let x = x.max(U256::from(1));
+            let difference = Float::from(ln_fixed(x)) - ln_float(x);
+            assert!(difference.clone().abs() < 1e-33, "ln of {} differs by {}", x, difference);
+        }
+    }
+
+    // clvr_order computing the objective with native fixed point numbers instead of rug
+    #[cfg(feature = "rug")]
+    fn clvr_order_fixed(model: &dyn Model, p_0: U256, omega: &mut Omega<Trade>) -> Vec<usize> {
+        let ln_p0 = ln_fixed(p_0);
+        let mut arrival: Vec<usize> = (1..omega.len() + 1).collect();
+
+        for t in 1..omega.len() + 1 {
+            let prefix = model.after(omega, t - 1);
+            let mut best: Option<(usize, Fixed)> = None;
+            for i in t..omega.len() + 1 {
+                let (_, _, price) = prefix.simulate(&omega[i]);
+                let cost = (ln_p0.clone() - ln_fixed(price)).square();
+                if best.as_ref().is_none_or(|(_, best_cost)| cost < *best_cost) {
+                    best = Some((i, cost));
+                }
+            }
+
+            let (i, _) = best.unwrap();
+            omega.swap(i, t);
+            arrival.swap(i - 1, t - 1);
+        }
+
+        arrival
+    }
+
+    #[cfg(feature = "rug")]
+    fn random_batch(n: usize, seed: u64) -> Omega<Trade> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut omega = Omega::new();
+        for _ in 0..n {
+            let amount = U256::from(rng.gen_range(1_000_000_000_000_000u128..20_000_000_000_000_000_000u128));
+            let direction = if rng.gen() { TradeDirection::Buy } else { TradeDirection::Sell };
+            omega.push(Trade::new(amount, direction));
+        }
+        omega
+    }
+
+    #[cfg(feature = "rug")]
+    #[test]
+    fn test_orderings_match_rug() {
+        let mut rng = StdRng::seed_from_u64(43);
+        for seed in 0..20 {
+            let model = CLVRModel::new(size(rng.gen_range(100..1000)), size(rng.gen_range(100..1000)));
+            let p_0 = model.P(&Omega::<Trade>::new(), 0);
+            let n = rng.gen_range(2..30);
+
+            let mut omega = random_batch(n, seed);
+            let mut omega_fixed = random_batch(n, seed);
+            let order = clvr_order(&model, p_0, &mut omega, None, &Weights::default());
+            assert_eq!(order, clvr_order_fixed(&model, p_0, &mut omega_fixed));
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::clvr::model::Model;
+use crate::trades::implementation::Trade;
+use crate::trades::ITrade;
+use alloy::primitives::{aliases::U24, U256};
+
+// part of trade for the exact amount, in for an exact input trade and out for an exact output one, executed in the pool
+// with the given fee tier. The part keeps the trade's price limit and its share of the trade's bounds: an exact input
+// part must receive its share of the minimum out, an exact output part may pay its share of the maximum in
+pub fn part_of(trade: &dyn ITrade, amount: U256, fee: U24) -> Trade {
+    let part = match trade.get_amount_out() {
+        None => {
+            let amount_out_minimum = trade.get_amount_out_minimum() * amount / trade.get_amount_in();
+            Trade::new(amount, trade.get_direction()).with_amount_out_minimum(amount_out_minimum)
+        }
+        Some(amount_out) => {
+            let amount_in_maximum = trade.get_amount_in() * amount / amount_out;
+            Trade::new_exact_output(amount, amount_in_maximum, trade.get_direction())
+        }
+    };
+
+    match trade.get_sqrt_price_limit_x96() {
+        Some(limit) => part.with_fee(fee).with_sqrt_price_limit_x96(limit),
+        None => part.with_fee(fee),
+    }
+}
+
+// Splits trade across the pools of its pair with different fee tiers, tiers[k] being the fee of a pool and its model
+// once its pending batch is executed. The trade's exact amount is cut into parts equal parts, each going to the tier
+// where it adds the most output (exact input) or costs the least input (exact output) on top of the parts routed there.
+// A single part selects the tier with the best predicted output. Returns the amount routed to every tier
+pub fn route(tiers: &[(U24, &dyn Model)], trade: &dyn ITrade, parts: usize) -> Vec<U256> {
+    let mut routed = vec![U256::ZERO; tiers.len()];
+    if tiers.is_empty() || parts == 0 {
+        return routed;
+    }
+
+    let exact_output = trade.get_amount_out().is_some();
+    let amount = trade.get_amount_out().unwrap_or(trade.get_amount_in());
+    let chunk = amount / U256::from(parts);
+    // amounts in and out of what is routed to every tier so far
+    let mut executed = vec![(U256::ZERO, U256::ZERO); tiers.len()];
+
+    for p in 0..parts {
+        // the last part takes the rounding remainder
+        let chunk = if p == parts - 1 { amount - chunk * U256::from(parts - 1) } else { chunk };
+        if chunk.is_zero() {
+            continue;
+        }
+
+        let mut best: Option<(usize, (U256, U256), U256)> = None;
+        for (k, &(fee, model)) in tiers.iter().enumerate() {
+            let (amount_in, amount_out, _) = model.simulate(&part_of(trade, routed[k] + chunk, fee));
+            let (before_in, before_out) = executed[k];
+            let better = if exact_output {
+                // a pool which cannot deliver the part is skipped
+                if amount_out < routed[k] + chunk {
+                    continue;
+                }
+                let cost = amount_in.saturating_sub(before_in);
+                best.as_ref().is_none_or(|&(_, _, best)| cost < best).then_some(cost)
+            } else {
+                let gain = amount_out.saturating_sub(before_out);
+                best.as_ref().is_none_or(|&(_, _, best)| gain > best).then_some(gain)
+            };
+            if let Some(score) = better {
+                best = Some((k, (amount_in, amount_out), score));
+            }
+        }
+
+        if let Some((k, amounts, _)) = best {
+            routed[k] += chunk;
+            executed[k] = amounts;
+        }
+    }
+
+    routed
+}
@@ -0,0 +1,74 @@
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::{Model, Omega};
+use crate::clvr::routing::{part_of, route};
+use crate::trades::implementation::Trade;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, U160, U256};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    #[test]
+    fn test_route_selects_tier() {
+        let thin = CLVRModel::new(size(10), size(10));
+        let deep = CLVRModel::new(size(1000), size(1000));
+        let tiers: Vec<(U24, &dyn Model)> = vec![(U24::from(100), &thin), (U24::from(3000), &deep)];
+
+        // the deep pool delivers more despite its higher fee
+        let sell = Trade::new(size(5), TradeDirection::Sell);
+        assert_eq!(route(&tiers, &sell, 1), vec![U256::ZERO, size(5)]);
+        let buy = Trade::new_exact_output(size(5), size(10), TradeDirection::Buy);
+        assert_eq!(route(&tiers, &buy, 1), vec![U256::ZERO, size(5)]);
+
+        // the pending batch of a pool is part of the prediction
+        let pool = CLVRModel::new(size(100), size(100));
+        let pending = Omega::new_from(vec![Trade::new(size(50), TradeDirection::Sell)]);
+        let after = pool.after(&pending, 1);
+        let tiers: Vec<(U24, &dyn Model)> = vec![(U24::from(500), after.as_ref()), (U24::from(500), &pool)];
+        assert_eq!(route(&tiers, &sell, 1), vec![U256::ZERO, size(5)]);
+    }
+
+    #[test]
+    fn test_route_splits_across_tiers() {
+        let pool = CLVRModel::new(size(100), size(100));
+        let tiers: Vec<(U24, &dyn Model)> = vec![(U24::from(500), &pool), (U24::from(500), &pool)];
+        let sell = Trade::new(size(20), TradeDirection::Sell);
+
+        // equal pools take half each, which delivers more than a single pool
+        let routed = route(&tiers, &sell, 10);
+        assert_eq!(routed, vec![size(10), size(10)]);
+        let single = pool.simulate(&Trade::new(size(20), TradeDirection::Sell).with_fee(U24::from(500))).1;
+        let half = pool.simulate(&Trade::new(size(10), TradeDirection::Sell).with_fee(U24::from(500))).1;
+        assert!(half + half > single);
+
+        assert!(route(&[], &sell, 10).is_empty());
+    }
+
+    #[test]
+    fn test_part_keeps_bounds() {
+        let limit = U160::from(1) << 95;
+        let sell = Trade::new(size(10), TradeDirection::Sell)
+            .with_amount_out_minimum(size(8))
+            .with_sqrt_price_limit_x96(limit);
+
+        // a quarter of the trade must receive a quarter of its minimum, within the same price limit
+        let part = part_of(&sell, size(10) / U256::from(4), U24::from(500));
+        assert_eq!(part.get_amount_out_minimum(), size(2));
+        assert_eq!(part.get_sqrt_price_limit_x96(), Some(limit));
+        assert_eq!(part.get_fee(), U24::from(500));
+
+        let buy = Trade::new_exact_output(size(10), size(20), TradeDirection::Buy);
+        let part = part_of(&buy, size(5), U24::from(3000));
+        assert_eq!(part.get_amount_out(), Some(size(5)));
+        assert_eq!(part.get_amount_in(), size(10));
+        assert_eq!(part.get_sqrt_price_limit_x96(), None);
+    }
+}
@@ -0,0 +1,106 @@
+use crate::clvr::algorithm::{candidate_cost, ln, update_worst, Weights};
+use crate::clvr::metrics::{quote, Quote};
+use crate::clvr::model::{Model, Omega, SingleTrade, TradeId};
+use crate::clvr::real::{self, Real};
+use crate::trades::ITrade;
+use alloy::primitives::U256;
+
+// whether the t'th trade would revert on-chain: a trade the pool rejects (e.g. a price limit on the wrong side of
+// the price), an exact input trade receiving less than its amount_out_minimum, or an exact output trade that cannot
+// be filled within its maximum amount in.
+// NOTE: as in the router, an exact output trade with a price limit may be partially filled
+pub fn reverts<T: ITrade>(model: &dyn Model, omega: &Omega<T>, t: usize) -> bool {
+    model.reverts(omega, t) || unfilled(&omega[t], &quote(model, omega, t))
+}
+
+// whether the router rejects the fill of trade, a minimum out or maximum in it does not meet
+fn unfilled(trade: &dyn ITrade, quote: &Quote) -> bool {
+    match trade.get_amount_out() {
+        None => quote.amount_out < trade.get_amount_out_minimum(),
+        Some(_) => (quote.partial && trade.get_sqrt_price_limit_x96().is_none()) || quote.amount_in > trade.get_amount_in(),
+    }
+}
+
+// Pool, weighted objective, worst slippage and number of reverting trades once the first trades of an ordering are
+// executed, advanced one trade at a time
+struct Prefix {
+    pool: Box<dyn Model>,
+    value: Real,
+    worst: Option<Real>,
+    reverting: usize,
+}
+
+impl Prefix {
+    fn new<T: ITrade>(model: &dyn Model, omega: &Omega<T>) -> Self {
+        Prefix {
+            pool: model.after(omega, 0),
+            value: real::zero(),
+            worst: None,
+            reverting: 0,
+        }
+    }
+
+    // the prefix followed by trade, which reverts as in reverts
+    fn then(&self, trade: &dyn ITrade, ln_p0: &Real, weights: &Weights) -> Self {
+        let (cost, surplus) = candidate_cost(self.pool.as_ref(), ln_p0, trade, weights, self.worst.as_ref());
+        let mut worst = self.worst.clone();
+        update_worst(surplus, &mut worst);
+        let (amount_in, amount_out, _) = self.pool.simulate(trade);
+        let reverts = self.pool.reverts(&SingleTrade(trade), 1) || unfilled(trade, &Quote::new(trade, amount_in, amount_out));
+
+        Prefix {
+            pool: self.pool.after(&SingleTrade(trade), 1),
+            value: self.value.clone() + cost,
+            worst,
+            reverting: self.reverting + reverts as usize,
+        }
+    }
+}
+
+// Repairs an ordering so that no trade reverts. The first reverting trade is moved to the position with the lowest
+// weighted objective among those reducing the number of reverting trades, or excluded if no such position exists.
+// Returns the excluded trades with their ids, they are deferred to the next batch.
+// NOTE: moving a trade leaves the trades before both its positions untouched, they are not executed again
+pub fn enforce_slippage<T: ITrade>(model: &dyn Model, p_0: U256, omega: &mut Omega<T>, weights: &Weights) -> Vec<(TradeId, T)> {
+    let ln_p0 = ln(p_0);
+    let mut deferred = Vec::new();
+
+    loop {
+        let mut prefixes = vec![Prefix::new(model, omega)];
+        for t in 1..omega.len() + 1 {
+            let next = prefixes[t - 1].then(&omega[t], &ln_p0, weights);
+            prefixes.push(next);
+        }
+        let reverting = prefixes[omega.len()].reverting;
+        let Some(t) = (1..omega.len() + 1).find(|&t| prefixes[t].reverting > prefixes[t - 1].reverting) else {
+            break;
+        };
+
+        // try the trade at every other position, from the first position the move changes on
+        let mut best: Option<(usize, Real)> = None;
+        for p in (1..omega.len() + 1).filter(|&p| p != t) {
+            let mut order: Vec<usize> = (1..omega.len() + 1).filter(|&i| i != t).collect();
+            order.insert(p - 1, t);
+
+            let start = t.min(p) - 1;
+            let mut moved = prefixes[start].then(&omega[order[start]], &ln_p0, weights);
+            for &i in &order[start + 1..] {
+                if moved.reverting >= reverting {
+                    break; // reverting trades only add up
+                }
+                moved = moved.then(&omega[i], &ln_p0, weights);
+            }
+
+            if moved.reverting < reverting && best.as_ref().is_none_or(|(_, best_value)| moved.value < *best_value) {
+                best = Some((p, moved.value));
+            }
+        }
+
+        match best {
+            Some((p, _)) => omega.move_to(t, p),
+            None => deferred.push((omega.id(t), omega.remove(t))),
+        }
+    }
+
+    deferred
+}
@@ -0,0 +1,143 @@
+use crate::clvr::algorithm::{weighted_objective, Weights};
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::v3_model::V3Model;
+use crate::clvr::model::{Model, Omega, TradeId};
+use crate::clvr::real::Real;
+use crate::clvr::slippage::{enforce_slippage, reverts};
+use crate::trades::implementation::Trade;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, U160, U256};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use uniswap_v3_sdk::prelude::Tick;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEI: &str = "000000000000000000";
+
+    fn size(x: u128) -> U256 {
+        let size: String = x.to_string() + WEI;
+        U256::from_str_radix(&size, 10).unwrap()
+    }
+
+    fn model() -> CLVRModel {
+        CLVRModel::new(size(100), size(100))
+    }
+
+    fn trade(amount_in: u128, direction: TradeDirection, amount_out_minimum: u128) -> Trade {
+        Trade::new(size(amount_in), direction)
+            .with_fee(U24::ZERO)
+            .with_amount_out_minimum(size(amount_out_minimum))
+    }
+
+    #[test]
+    fn test_slippage_repositions_trade() {
+        let model = model();
+        let mut omega = Omega::new();
+        // selling 10 x first yields ~9.09 y, after the buy it yields ~10.9 y
+        omega.push(trade(10, TradeDirection::Sell, 10));
+        omega.push(trade(10, TradeDirection::Buy, 0));
+        assert!(reverts(&model, &omega, 1));
+
+        let deferred = enforce_slippage(&model, size(1), &mut omega, &Weights::default());
+
+        assert!(deferred.is_empty());
+        assert_eq!(omega.len(), 2);
+        assert_eq!(omega[1].get_direction(), TradeDirection::Buy);
+        assert!(!reverts(&model, &omega, 2));
+    }
+
+    #[test]
+    fn test_slippage_defers_unfillable_trade() {
+        let model = model();
+        let mut omega = Omega::new();
+        let unfillable = omega.push(trade(10, TradeDirection::Sell, 20));
+        omega.push(trade(5, TradeDirection::Buy, 0));
+        omega.push(trade(3, TradeDirection::Sell, 0));
+
+        let deferred = enforce_slippage(&model, size(1), &mut omega, &Weights::default());
+
+        // the deferred trade keeps its id, so that it can be traced back to its submission
+        assert_eq!(deferred.len(), 1);
+        assert_eq!(deferred[0].0, unfillable);
+        assert_eq!(deferred[0].1.get_amount_in(), size(10));
+        assert_eq!(omega.len(), 2);
+        assert!((1..omega.len() + 1).all(|t| !reverts(&model, &omega, t)));
+    }
+
+    // enforce_slippage as it was before prefixes were reused: the reverting trades of the whole ordering are counted
+    // and it is costed again at every position tried
+    fn reference_enforce_slippage(model: &dyn Model, p_0: U256, omega: &mut Omega<Trade>, weights: &Weights) -> Vec<(TradeId, Trade)> {
+        let count_reverts = |omega: &Omega<Trade>| (1..omega.len() + 1).filter(|&t| reverts(model, omega, t)).count();
+        let mut deferred = Vec::new();
+
+        loop {
+            let reverting = count_reverts(omega);
+            let Some(t) = (1..omega.len() + 1).find(|&t| reverts(model, omega, t)) else {
+                break;
+            };
+
+            let mut at = t;
+            let mut best: Option<(usize, Real)> = None;
+            for p in (1..omega.len() + 1).filter(|&p| p != t) {
+                omega.move_to(at, p);
+                at = p;
+                if count_reverts(omega) < reverting {
+                    let value = weighted_objective(model, p_0, omega, weights);
+                    if best.as_ref().is_none_or(|(_, best_value)| value < *best_value) {
+                        best = Some((p, value));
+                    }
+                }
+            }
+
+            match best {
+                Some((p, _)) => omega.move_to(at, p),
+                None => deferred.push((omega.id(at), omega.remove(at))),
+            }
+        }
+
+        deferred
+    }
+
+    // random trades between 1 and 10 tokens, most asking for about what they would get alone against the pool
+    fn random_batch(model: &dyn Model, n: usize, seed: u64) -> Omega<Trade> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut omega = Omega::new();
+        for _ in 0..n {
+            let direction = if rng.gen() { TradeDirection::Buy } else { TradeDirection::Sell };
+            let trade = Trade::new(size(rng.gen_range(1..10)), direction).with_fee(U24::ZERO);
+            let alone = model.simulate(&trade).1;
+            let minimum = alone * U256::from(rng.gen_range(90..102)) / U256::from(100);
+            omega.push(trade.with_amount_out_minimum(minimum));
+        }
+        omega
+    }
+
+    #[test]
+    fn test_slippage_matches_reference() {
+        let liquidity = 100_000_000_000_000_000_000u128;
+        let ticks = vec![Tick::new(-887220, liquidity, liquidity as i128), Tick::new(887220, liquidity, -(liquidity as i128))];
+        let clvr_model = model();
+        let v3_model = V3Model::new(U160::from(1) << 96, liquidity, 0, U24::ZERO, ticks);
+        let models: [&dyn Model; 2] = [&clvr_model, &v3_model];
+        let weights = Weights {
+            volatility: 1.0,
+            surplus: 0.5,
+            slippage: 0.5,
+        };
+
+        for model in models {
+            let mut expected = random_batch(model, 12, 5);
+            let expected_deferred = reference_enforce_slippage(model, size(1), &mut expected, &weights);
+            let mut omega = random_batch(model, 12, 5);
+            let deferred = enforce_slippage(model, size(1), &mut omega, &weights);
+
+            assert!(!expected_deferred.is_empty() && expected.len() > 1);
+            let ids = |deferred: &[(TradeId, Trade)]| deferred.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+            assert_eq!(ids(&deferred), ids(&expected_deferred));
+            assert_eq!(omega.ids().collect::<Vec<_>>(), expected.ids().collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::clvr::algorithm::{clvr_order, Weights};
+use crate::clvr::auction::{uniform_clearing, uniform_residual};
+use crate::clvr::exact::{clvr_order_exact, exact_max_trades};
+use crate::clvr::local_search::clvr_refine;
+use crate::clvr::metrics::{quotes, Quote};
+use crate::clvr::model::{Model, Omega};
+use crate::trades::implementation::Trade;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::U256;
+use rand::seq::SliceRandom;
+use std::time::Duration;
+
+// Decides in which order the trades of a batch are executed against a pool
+pub trait OrderingStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn order(&self, model: &dyn Model, p_0: U256, omega: &mut Omega);
+
+    // what each trade of the ordered batch pays and receives, by default executing them one after the other
+    fn quotes(&self, model: &dyn Model, _p_0: U256, omega: &Omega) -> Vec<Quote> {
+        quotes(model, omega)
+    }
+
+    // weights of the objective the strategy minimizes, also used to report its orderings
+    fn weights(&self) -> Weights {
+        Weights::default()
+    }
+
+    // whether the ordered trades are executed against the pool one after the other, otherwise the batch is settled
+    // at once by pool_trades and the quotes
+    fn sequential(&self) -> bool {
+        true
+    }
+
+    // swaps the pool executes for the ordered batch, by default every trade in order
+    fn pool_trades(&self, _model: &dyn Model, _p_0: U256, omega: &Omega) -> Vec<Trade> {
+        omega.iter().map(|trade| Trade::from(trade.as_ref())).collect()
+    }
+}
+
+// Options of the configurable strategies
+#[derive(Clone, Default)]
+pub struct StrategyConfig {
+    pub refine_budget: Option<Duration>, // time spent improving the CLVR greedy by local search
+    pub max_displacement: Option<usize>, // furthest a trade may move from its arrival slot
+    pub weights: Weights,
+}
+
+// CLVR greedy, optionally improved by local search within refine_budget.
+// With max_displacement every trade stays within that many positions of its arrival slot
+pub struct CLVRGreedy {
+    pub refine_budget: Option<Duration>,
+    pub max_displacement: Option<usize>,
+    pub weights: Weights,
+}
+
+impl OrderingStrategy for CLVRGreedy {
+    fn name(&self) -> &'static str {
+        "clvr"
+    }
+
+    fn order(&self, model: &dyn Model, p_0: U256, omega: &mut Omega) {
+        let arrival = clvr_order(model, p_0, omega, self.max_displacement, &self.weights);
+        if let Some(budget) = self.refine_budget {
+            let max_displacement = self.max_displacement.map(|k| (k, arrival.as_slice()));
+            clvr_refine(model, p_0, omega, budget, max_displacement, &self.weights);
+        }
+    }
+
+    fn weights(&self) -> Weights {
+        self.weights
+    }
+}
+
+// Exact CLVR optimum under the same weights and max_displacement as the greedy, batches larger than
+// exact_max_trades fall back to the greedy
+pub struct ExactOptimal {
+    pub max_displacement: Option<usize>,
+    pub weights: Weights,
+}
+
+impl OrderingStrategy for ExactOptimal {
+    fn name(&self) -> &'static str {
+        "exact"
+    }
+
+    fn order(&self, model: &dyn Model, p_0: U256, omega: &mut Omega) {
+        if omega.len() <= exact_max_trades(&self.weights) {
+            clvr_order_exact(model, p_0, omega, self.max_displacement, &self.weights);
+        } else {
+            clvr_order(model, p_0, omega, self.max_displacement, &self.weights);
+        }
+    }
+
+    fn weights(&self) -> Weights {
+        self.weights
+    }
+}
+
+// Arrival order, omega is filled in the order trades were submitted
+pub struct Fifo;
+
+impl OrderingStrategy for Fifo {
+    fn name(&self) -> &'static str {
+        "fifo"
+    }
+
+    fn order(&self, _: &dyn Model, _: U256, _: &mut Omega) {}
+}
+
+// Uniformly random order
+pub struct RandomOrder;
+
+impl OrderingStrategy for RandomOrder {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn order(&self, _: &dyn Model, _: U256, omega: &mut Omega) {
+        let mut order: Vec<usize> = (1..omega.len() + 1).collect();
+        order.shuffle(&mut rand::thread_rng());
+        omega.reorder(&order);
+    }
+}
+
+// Smallest trades first, sizes are compared in tokens y by valuing sells at p_0
+pub struct VolumeSorted;
+
+impl OrderingStrategy for VolumeSorted {
+    fn name(&self) -> &'static str {
+        "volume"
+    }
+
+    fn order(&self, model: &dyn Model, p_0: U256, omega: &mut Omega) {
+        // p_0 is per whole token, so raw amounts of x are rescaled to raw amounts of y
+        let ten = U256::from(10);
+        let (decimals_x, decimals_y) = model.decimals();
+        let scale_x = ten.pow(U256::from(18 + decimals_x as u64));
+        let scale_y = ten.pow(U256::from(decimals_y));
+
+        let volume = |i: usize| match omega[i].get_direction() {
+            TradeDirection::Buy => omega[i].get_amount_in(),
+            TradeDirection::Sell => omega[i].get_amount_in() * p_0 * scale_y / scale_x,
+        };
+
+        let mut order: Vec<usize> = (1..omega.len() + 1).collect();
+        order.sort_by_key(|&i| volume(i)); // stable, ties keep arrival order
+        omega.reorder(&order);
+    }
+}
+
+// Every trade of the batch receives the same clearing price, only the net flow is executed against the pool.
+// The order of omega is kept, it does not change what the trades receive
+pub struct UniformClearing;
+
+impl OrderingStrategy for UniformClearing {
+    fn name(&self) -> &'static str {
+        "uniform"
+    }
+
+    fn order(&self, _: &dyn Model, _: U256, _: &mut Omega) {}
+
+    fn quotes(&self, model: &dyn Model, p_0: U256, omega: &Omega) -> Vec<Quote> {
+        uniform_clearing(model, p_0, omega)
+    }
+
+    fn sequential(&self) -> bool {
+        false
+    }
+
+    // only the net flow
+    fn pool_trades(&self, model: &dyn Model, p_0: U256, omega: &Omega) -> Vec<Trade> {
+        uniform_residual(model, p_0, omega).into_iter().collect()
+    }
+}
+
+// Resolves a strategy by the name used in configuration
+pub fn from_name(name: &str, config: &StrategyConfig) -> Option<Box<dyn OrderingStrategy>> {
+    match name {
+        "clvr" => Some(Box::new(CLVRGreedy {
+            refine_budget: config.refine_budget,
+            max_displacement: config.max_displacement,
+            weights: config.weights,
+        })),
+        "exact" => Some(Box::new(ExactOptimal {
+            max_displacement: config.max_displacement,
+            weights: config.weights,
+        })),
+        "fifo" => Some(Box::new(Fifo)),
+        "random" => Some(Box::new(RandomOrder)),
+        "volume" => Some(Box::new(VolumeSorted)),
+        "uniform" => Some(Box::new(UniformClearing)),
+        _ => None,
+    }
+}
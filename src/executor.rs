@@ -1,36 +1,288 @@
+use std::collections::HashMap;
+use std::ops::Range;
 use std::time::Duration;
 
-use alloy::{providers::{Provider, ProviderBuilder, RootProvider}, rpc::types::TransactionRequest, transports::http::{Client, Http}};
-use log::info;
-use tokio::time::sleep;
-use crate::server::{handlers::ScheduledDatabase, tokens::{USDC, USDT}, Processor};
+use alloy::{network::EthereumWallet, primitives::{aliases::U24, Address, Bytes, B256, U256}, providers::{Provider, ProviderBuilder, RootProvider}, rpc::types::TransactionRequest, signers::local::PrivateKeySigner, transports::http::{Client, Http}};
+use log::{error, info};
+use tokio::time::timeout;
+use crate::clvr::algorithm::Weights;
+use crate::clvr::metrics::Quote;
+use crate::clvr::model::multi_pool_model::MultiPoolModel;
+use crate::clvr::model::{Model, Omega, TradeId};
+use crate::clvr::netting::Fill;
+use crate::clvr::joint::{clvr_order_joint, enforce_joint_slippage, exact_input_legs, joint_objective};
+use crate::clvr::routing;
+use crate::trades::implementation::Trade;
+use crate::trades::path::Hop;
+use crate::trades::ITrade;
+use crate::server::batch::Batch;
+use crate::server::handlers_types::PlannedQuote;
+use crate::server::swap_router_v2;
+use crate::server::swap_router_v3::SwapParams;
+use crate::server::clvr_router::{self, IClvrRouter::Call};
+use crate::server::v4_settlement::{execute_calldata, settle_calldata, PoolKey};
+use crate::clvr::strategy::{self, OrderingStrategy, StrategyConfig};
+use crate::server::{handlers::{Arrivals, QuoteBook, ScheduledDatabase}, tokens::{USDC, USDT}, Processor};
 use crate::pool_fetcher::PoolFetcher;
+use crate::reference_price::{self, ReferencePrice};
 pub type QueryTransport = Http<Client>;
+use crate::pool_fetcher::curve::{self, CurvePoolFetcher};
+use crate::pool_fetcher::v2::{V2PoolFetcher, UNISWAP_V2_FACTORY, UNISWAP_V2_INIT_CODE_HASH};
 use crate::pool_fetcher::v3::V3PoolFetcher;
+use crate::pool_fetcher::v4::V4PoolFetcher;
 
 // Executor is responsible for waiting for the scheduled batch to be ready, and then executing the batch
 pub struct Executor {
     provider: RootProvider<QueryTransport>,
+    sender: Box<dyn Provider<QueryTransport>>, // signs and sends the transactions executing the batches
     pool_fetcher: Box<dyn PoolFetcher>,
 
     scheduled_db: ScheduledDatabase,
+    quote_book: QuoteBook,
+    arrivals: Arrivals,
 
     block_period: u64,
     last_batch_block: u64,
+
+    default_strategy: String,
+    default_reference_price: String,
+    strategy_config: StrategyConfig,
+    netting: bool, // match opposite trades of a pool's batch internally, only V4 batches can be settled netted
+    online: bool, // plan the trades of every pool as they arrive instead of ordering the batch when it closes
+    open: Batch, // trades of the open batch taken in so far, when online
+    plans: HashMap<Address, Plan>, // plan of every pool of the open batch, when online
+    linked_pools: Vec<Vec<Address>>, // pools sharing a token whose trades are ordered jointly
+    fee_tier_parts: usize, // parts a trade without a fee tier is split into across the pools of its pair
+    v4_settlement: Option<Address>, // settlement contract executing the batches of V4 pools
+    v2_router: Option<Address>, // router executing the batches of V2 pairs
+    v3_router: Option<Address>, // router executing the batches of V3 pools
+    clvr_router: Option<Address>, // contract executing the batches of V2, V3 and Curve pools through their routers
+}
+
+// plan of the trades of a pool of the open batch, built as they arrive, with the leg of every trade
+struct Plan {
+    processor: Processor,
+    legs: Vec<(TradeId, usize)>,
+}
+
+// call of a contract by the executor
+fn transaction(to: Address, calldata: Bytes) -> TransactionRequest {
+    TransactionRequest::default().to(to).input(calldata.into())
+}
+
+// transaction executing part of a batch, with the legs each of its calls executes in order
+struct Submission {
+    to: Address,
+    calldata: Bytes,
+    calls: Vec<Vec<usize>>,
 }
 
 impl Executor {
-    pub fn new(scheduled_db: ScheduledDatabase) -> Self {
+    pub fn new(scheduled_db: ScheduledDatabase, quote_book: QuoteBook, arrivals: Arrivals) -> Self {
         let block_period = std::env::var("BATCH_SUBMISSION_PERIOD_BLOCKS")
             .expect("BATCH_SUBMISSION_PERIOD_BLOCKS must be set")
             .parse::<u64>()
             .expect("BATCH_SUBMISSION_PERIOD_BLOCKS must be a valid number");
 
         let provider = Self::create_provider();
+        let signer = std::env::var("EXECUTOR_PRIVATE_KEY")
+            .expect("EXECUTOR_PRIVATE_KEY must be set")
+            .parse::<PrivateKeySigner>()
+            .expect("EXECUTOR_PRIVATE_KEY must be a valid private key");
+        let sender = Self::create_sender(signer);
 
-        let pool_fetcher = Box::new(V3PoolFetcher::new());
+        // V2 pairs and V3 pools are executed through their router, V4 pools are read through the StateView lens and their batches
+        // executed by the settlement contract, Curve pools execute their swaps themselves. Batches not settled by the
+        // settlement contract are executed by the router contract, which calls the routers and Curve pools
+        let address = |name: &str| {
+            std::env::var(name)
+                .unwrap_or_else(|_| panic!("{} must be set", name))
+                .parse::<Address>()
+                .unwrap_or_else(|_| panic!("{} must be a valid address", name))
+        };
+        let optional = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let (pool_fetcher, v2_router, v3_router, v4_settlement): (Box<dyn PoolFetcher>, Option<Address>, Option<Address>, Option<Address>) = match std::env::var("UNISWAP_VERSION").unwrap_or("v3".to_string()).as_str() {
+            "v2" => {
+                let factory = optional("V2_FACTORY_ADDRESS").map_or(UNISWAP_V2_FACTORY, |_| address("V2_FACTORY_ADDRESS"));
+                let init_code_hash = optional("V2_INIT_CODE_HASH").map_or(UNISWAP_V2_INIT_CODE_HASH, |hash| {
+                    hash.parse::<B256>().expect("V2_INIT_CODE_HASH must be a valid hash")
+                });
+                (Box::new(V2PoolFetcher::new(factory, init_code_hash)), Some(address("V2_ROUTER_ADDRESS")), None, None)
+            }
+            "v3" => (Box::new(V3PoolFetcher::new()), None, Some(address("SWAP_ROUTER_ADDRESS")), None),
+            "v4" => {
+                let hooks = optional("V4_HOOKS").map_or(Address::ZERO, |_| address("V4_HOOKS"));
+                (Box::new(V4PoolFetcher::new(address("V4_STATE_VIEW_ADDRESS"), hooks)), None, None, Some(address("V4_SETTLEMENT_ADDRESS")))
+            }
+            "curve" => {
+                // pools given as pool:coin0:coin1 separated by ',', the coins in the pool's order
+                let pools = std::env::var("CURVE_POOLS")
+                    .expect("CURVE_POOLS must be set")
+                    .split(',')
+                    .filter(|pool| !pool.trim().is_empty())
+                    .map(|pool| {
+                        let addresses: Vec<Address> = pool
+                            .split(':')
+                            .map(|address| address.trim().parse::<Address>().expect("CURVE_POOLS must be pools given as pool:coin0:coin1"))
+                            .collect();
+                        match addresses[..] {
+                            [pool, coin0, coin1] => (pool, (coin0, coin1)),
+                            _ => panic!("CURVE_POOLS must be pools given as pool:coin0:coin1"),
+                        }
+                    })
+                    .collect();
+                (Box::new(CurvePoolFetcher::new(pools)), None, None, None)
+            }
+            _ => panic!("UNISWAP_VERSION must be one of v2, v3, v4, curve"),
+        };
+        let clvr_router = v4_settlement.is_none().then(|| address("CLVR_ROUTER_ADDRESS"));
 
-        Self { provider, pool_fetcher, scheduled_db, block_period, last_batch_block: 0}
+        let default_strategy = std::env::var("ORDERING_STRATEGY").unwrap_or("clvr".to_string());
+        let default_reference_price = std::env::var("REFERENCE_PRICE").unwrap_or("spot".to_string());
+        Self::reference_price_from_config(&default_reference_price, v3_router.is_some());
+        let refine_budget = std::env::var("CLVR_REFINE_BUDGET_MS").ok().map(|ms| {
+            Duration::from_millis(ms.parse::<u64>().expect("CLVR_REFINE_BUDGET_MS must be a valid number"))
+        });
+        let max_displacement = std::env::var("CLVR_MAX_DISPLACEMENT").ok().map(|k| {
+            k.parse::<usize>().expect("CLVR_MAX_DISPLACEMENT must be a valid number")
+        });
+        let weight = |name: &str, default: f64| {
+            std::env::var(name).map_or(default, |w| w.parse::<f64>().unwrap_or_else(|_| panic!("{} must be a valid number", name)))
+        };
+        let weights = Weights {
+            volatility: weight("CLVR_WEIGHT_VOLATILITY", 1.0),
+            surplus: weight("CLVR_WEIGHT_SURPLUS", 0.0),
+            slippage: weight("CLVR_WEIGHT_SLIPPAGE", 0.0),
+        };
+        let strategy_config = StrategyConfig { refine_budget, max_displacement, weights };
+        Self::strategy_from_config(&default_strategy, &strategy_config, v4_settlement.is_some());
+
+        // a netted batch is settled at once, which only the V4 settlement contract does
+        let netting = std::env::var("CLVR_NETTING").is_ok_and(|netting| {
+            netting.parse::<bool>().expect("CLVR_NETTING must be true or false")
+        });
+        if netting && v4_settlement.is_none() {
+            panic!("CLVR_NETTING is only available with UNISWAP_VERSION v4");
+        }
+        let online = std::env::var("CLVR_ONLINE").is_ok_and(|online| {
+            online.parse::<bool>().expect("CLVR_ONLINE must be true or false")
+        });
+
+        // groups of pools separated by ';', the pools of a group by ','
+        let linked_pools = std::env::var("LINKED_POOLS").map_or(Vec::new(), |groups| {
+            groups
+                .split(';')
+                .filter(|group| !group.trim().is_empty())
+                .map(|group| {
+                    group
+                        .split(',')
+                        .map(|pool| pool.trim().parse::<Address>().expect("LINKED_POOLS must be groups of pool addresses"))
+                        .collect()
+                })
+                .collect()
+        });
+
+        let fee_tier_parts = std::env::var("FEE_TIER_SPLIT_PARTS").map_or(1, |parts| {
+            parts.parse::<usize>().expect("FEE_TIER_SPLIT_PARTS must be a valid number")
+        });
+
+        Self { provider, sender, pool_fetcher, scheduled_db, quote_book, arrivals, block_period, last_batch_block: 0, default_strategy, default_reference_price, strategy_config, netting, online, open: Batch::default(), plans: HashMap::new(), linked_pools, fee_tier_parts, v4_settlement, v2_router, v3_router, clvr_router }
+    }
+
+    // strategy set for the pool by ORDERING_STRATEGY_<pool address>, otherwise ORDERING_STRATEGY
+    fn ordering_strategy(&self, pool: Address) -> Box<dyn OrderingStrategy> {
+        let name = std::env::var(format!("ORDERING_STRATEGY_{:x}", pool)).unwrap_or(self.default_strategy.clone());
+
+        Self::strategy_from_config(&name, &self.strategy_config, self.v4_settlement.is_some())
+    }
+
+    // a batch which is not executed trade by trade is settled at once, which only the V4 settlement contract does
+    fn strategy_from_config(name: &str, config: &StrategyConfig, v4: bool) -> Box<dyn OrderingStrategy> {
+        let strategy = strategy::from_name(name, config)
+            .expect("ORDERING_STRATEGY must be one of clvr, exact, fifo, random, volume, uniform");
+        if !strategy.sequential() && !v4 {
+            panic!("ORDERING_STRATEGY {} is only available with UNISWAP_VERSION v4", name);
+        }
+
+        strategy
+    }
+
+    // source of p_0 set for the pool by REFERENCE_PRICE_<pool address>, otherwise REFERENCE_PRICE
+    fn reference_price(&self, pool: Address) -> Box<dyn ReferencePrice> {
+        let config = std::env::var(format!("REFERENCE_PRICE_{:x}", pool)).unwrap_or(self.default_reference_price.clone());
+
+        Self::reference_price_from_config(&config, self.v3_router.is_some())
+    }
+
+    fn reference_price_from_config(config: &str, v3: bool) -> Box<dyn ReferencePrice> {
+        let reference_price = reference_price::from_config(config)
+            .expect("REFERENCE_PRICE must be one of spot, twap:<seconds>, chainlink:<aggregator>[:inverse], fixed:<p_0>");
+        if reference_price.requires_v3_oracle() && !v3 {
+            panic!("REFERENCE_PRICE {} is only available with UNISWAP_VERSION v3", reference_price.name());
+        }
+
+        reference_price
+    }
+
+    // model of the pool and its p_0, None if either cannot be read
+    async fn pool_state(&self, pool_address: Address) -> Option<(Box<dyn Model>, U256)> {
+        let reference_price = self.reference_price(pool_address);
+
+        let model = match self.pool_fetcher.get_model(self.provider.clone(), pool_address).await {
+            Ok(model) => model,
+            Err(e) => {
+                error!("Failed to fetch pool {}: {}", pool_address, e);
+                return None;
+            }
+        };
+        let p_0 = match reference_price.price(self.provider.clone(), pool_address, model.as_ref()).await {
+            Ok(p_0) => p_0,
+            Err(e) => {
+                error!("Failed to read the {} price of pool {}: {}", reference_price.name(), pool_address, e);
+                return None;
+            }
+        };
+
+        Some((model, p_0))
+    }
+
+    // Routes a trade submitted without a fee tier across the pools of its pair, given their pending batches. Returns the
+    // part of the trade executed in every selected pool
+    async fn route(&self, hop: &Hop, trade: &Trade, pending: &HashMap<Address, Omega>) -> Vec<(Address, Trade)> {
+        let pools = match self.pool_fetcher.get_pools(self.provider.clone(), hop.token_in, hop.token_out).await {
+            Ok(pools) => pools,
+            Err(e) => {
+                error!("Failed to find the pools of {} and {}: {}", hop.token_in, hop.token_out, e);
+                return Vec::new();
+            }
+        };
+
+        let mut tiers: Vec<(U24, Address, Box<dyn Model>)> = Vec::new();
+        for (fee, pool_address) in pools {
+            match self.pool_fetcher.get_model(self.provider.clone(), pool_address).await {
+                Ok(model) => {
+                    let model = match pending.get(&pool_address) {
+                        Some(batch) => model.after(batch, batch.len()),
+                        None => model,
+                    };
+                    tiers.push((fee, pool_address, model));
+                }
+                Err(e) => error!("Failed to fetch pool {}: {}", pool_address, e),
+            }
+        }
+
+        let models: Vec<(U24, &dyn Model)> = tiers.iter().map(|(fee, _, model)| (*fee, model.as_ref())).collect();
+        let routed = routing::route(&models, trade, self.fee_tier_parts);
+
+        tiers
+            .iter()
+            .zip(routed)
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|((fee, pool_address, _), amount)| {
+                info!("Routing {} of a trade of {} for {} to pool {} with fee {}", amount, hop.token_in, hop.token_out, pool_address, fee);
+                (*pool_address, routing::part_of(trade, amount, *fee))
+            })
+            .collect()
     }
 
     fn create_provider() -> RootProvider<QueryTransport> {
@@ -41,27 +293,489 @@ impl Executor {
         provider
     }
 
+    fn create_sender(signer: PrivateKeySigner) -> Box<dyn Provider<QueryTransport>> {
+        let rpc_url = std::env::var("ETHEREUM_RPC_URL").expect("ETHEREUM_RPC_URL must be set");
+        let rpc_url = rpc_url.parse().expect("ETHEREUM_RPC_URL must be a valid URL");
+        let sender = ProviderBuilder::new().with_recommended_fillers().wallet(EthereumWallet::from(signer)).on_http(rpc_url);
+
+        Box::new(sender)
+    }
+
+    // Decomposes the trades of the batch scheduled from first on into the legs executed against every pool, returns
+    // the legs added. Single pool trades without a fee tier are routed once the others are added, each on top of the
+    // trades already pending in the pools of its pair
+    async fn decompose(&self, batch: &mut Batch, first: usize) -> Range<usize> {
+        let legs = batch.legs.len();
+        let mut unrouted: Vec<(usize, Hop, Trade)> = Vec::new();
+        for s in first..batch.scheduled.len() {
+            let hops = batch.scheduled[s].swap_params.hops();
+            let Some(single_trade) = batch.scheduled[s].swap_params.single_trade() else {
+                self.add_path(batch, s).await;
+                continue;
+            };
+            if hops[0].fee.is_zero() {
+                unrouted.push((s, hops[0].clone(), single_trade));
+                continue;
+            }
+
+            let pool_address = self.pool_fetcher.get_pool_address(self.provider.clone(), hops[0].token_in, hops[0].token_out, hops[0].fee);
+            info!("Pool address: {}", pool_address);
+            batch.add_leg(s, pool_address, &hops[0], single_trade);
+        }
+
+        for (s, hop, trade) in unrouted {
+            for (pool_address, part) in self.route(&hop, &trade, &batch.pending()).await {
+                batch.add_leg(s, pool_address, &hop, part);
+            }
+        }
+
+        legs..batch.legs.len()
+    }
+
+    // Decomposes the multi-hop trade scheduled at s into one leg per hop, each selling what the previous one is quoted
+    // to buy on top of the trades pending in its pool, and registers them as a path. A trade whose pools cannot be read
+    // is scheduled again
+    async fn add_path(&self, batch: &mut Batch, s: usize) {
+        let SwapParams::ExactInput(params) = &batch.scheduled[s].swap_params else {
+            return;
+        };
+        let (amount_in, amount_out_minimum) = (params.amountIn, params.amountOutMinimum);
+        let hops = batch.scheduled[s].swap_params.hops();
+        let pending = batch.pending();
+
+        let mut pools = Vec::new();
+        let mut models = Vec::new();
+        for hop in &hops {
+            let pool_address = self.pool_fetcher.get_pool_address(self.provider.clone(), hop.token_in, hop.token_out, hop.fee);
+            match self.pool_fetcher.get_model(self.provider.clone(), pool_address).await {
+                Ok(model) => {
+                    let model = match pending.get(&pool_address) {
+                        Some(batch) => model.after(batch, batch.len()),
+                        None => model,
+                    };
+                    pools.push(pool_address);
+                    models.push(model);
+                }
+                Err(e) => {
+                    error!("Failed to fetch pool {} of a multi-hop trade: {}", pool_address, e);
+                    self.scheduled_db.lock().unwrap().push(batch.scheduled[s].clone());
+                    return;
+                }
+            }
+        }
+
+        let positions: Vec<usize> = (0..hops.len()).collect();
+        let trades = exact_input_legs(&MultiPoolModel::new(models), &hops, &positions, amount_in, amount_out_minimum);
+        info!("Multi-hop trade through pools {:?}", pools);
+        // legs are tagged with their pool when ordered jointly
+        let legs = trades
+            .into_iter()
+            .zip(hops.iter().zip(pools))
+            .map(|(trade, (hop, pool_address))| batch.add_leg(s, pool_address, hop, trade.with_pool(0)))
+            .collect();
+        batch.add_path(legs);
+    }
+
+    // Takes in the trades scheduled since the last poll: every leg is inserted into the plan of its pool, created with
+    // the pool's strategy by its first leg, and the quotes of the plan are published. A leg whose pool cannot be read is
+    // ordered when the batch closes, as are the legs of a path, ordered jointly
+    async fn intake(&mut self) {
+        let first = self.open.scheduled.len();
+        let arrived = self.scheduled_db.lock().unwrap()[first..].to_vec();
+        if arrived.is_empty() {
+            return;
+        }
+
+        let mut open = std::mem::take(&mut self.open);
+        open.scheduled.extend(arrived);
+        for leg in self.decompose(&mut open, first).await {
+            if open.path_of(leg).is_some() {
+                continue;
+            }
+            let pool_address = open.legs[leg].pool;
+            let Some((model, p_0)) = self.pool_state(pool_address).await else {
+                continue;
+            };
+
+            let mut plan = match self.plans.remove(&pool_address) {
+                Some(mut plan) => {
+                    plan.processor.update_model(model);
+                    plan
+                }
+                None => Plan { processor: self.processor(pool_address, model), legs: Vec::new() },
+            };
+            let id = plan.processor.insert_trade(Box::new(open.legs[leg].trade.clone()), p_0);
+            plan.legs.push((id, leg));
+            self.publish(&open, pool_address, &plan, p_0);
+            self.plans.insert(pool_address, plan);
+        }
+        self.open = open;
+    }
+
+    // publishes what every trade of the plan of a pool is quoted to pay and receive
+    fn publish(&self, batch: &Batch, pool_address: Address, plan: &Plan, p_0: U256) {
+        let ids: HashMap<TradeId, usize> = plan.legs.iter().copied().collect();
+        let quotes = plan
+            .processor
+            .plan()
+            .ids()
+            .zip(plan.processor.quotes(p_0))
+            .map(|(id, quote)| {
+                let owner = batch.owner(ids[&id]);
+                PlannedQuote {
+                    pool: pool_address,
+                    from: owner.from,
+                    recipient: owner.swap_params.recipient(),
+                    amount_in: quote.amount_in,
+                    amount_out: quote.amount_out,
+                }
+            })
+            .collect();
+        self.quote_book.lock().unwrap().insert(pool_address, quotes);
+    }
+
+    // processor of the batch of a pool with its strategy
+    fn processor(&self, pool_address: Address, model: Box<dyn Model>) -> Processor {
+        let processor = Processor::new(model, self.ordering_strategy(pool_address));
+        if self.netting {
+            return processor.with_netting();
+        }
+
+        processor
+    }
+
+    // Orders the legs of the batch executed in pool through a Processor, which excludes those that would revert and
+    // reports the ordering against the arrival order, then submits the ordered batch. A pool planned online closes
+    // its plan, legs missing from it are inserted first. The legs of paths are left to the joint ordering
+    async fn execute_pool(&self, batch: &Batch, pool_address: Address, plan: Option<Plan>) {
+        let legs: Vec<usize> = batch.legs_of(pool_address).into_iter().filter(|&leg| batch.path_of(leg).is_none()).collect();
+        if legs.is_empty() {
+            return;
+        }
+        let reference_price = self.reference_price(pool_address);
+        let Some((model, p_0)) = self.pool_state(pool_address).await else {
+            self.resubmit(batch, legs.iter().map(|&leg| (leg, &batch.legs[leg].trade as &dyn ITrade)));
+            return;
+        };
+
+        let (mut processor, legs, report) = match plan {
+            Some(Plan { mut processor, legs: mut planned }) => {
+                info!("Closing the plan of {} trades on pool {} at {} p_0 {}", legs.len(), pool_address, reference_price.name(), p_0);
+                processor.update_model(model);
+                let missing: Vec<usize> = legs.into_iter().filter(|&leg| planned.iter().all(|&(_, other)| other != leg)).collect();
+                for leg in missing {
+                    planned.push((processor.insert_trade(Box::new(batch.legs[leg].trade.clone()), p_0), leg));
+                }
+                let report = processor.order_plan(p_0);
+                (processor, planned, report)
+            }
+            None => {
+                let strategy_name = self.ordering_strategy(pool_address).name();
+                info!("Ordering {} trades on pool {} with strategy {} at {} p_0 {}", legs.len(), pool_address, strategy_name, reference_price.name(), p_0);
+                let mut processor = self.processor(pool_address, model);
+                let legs: Vec<(TradeId, usize)> = legs.into_iter().map(|leg| (processor.add_trade(Box::new(batch.legs[leg].trade.clone())), leg)).collect();
+                let report = processor.order(p_0);
+                (processor, legs, report)
+            }
+        };
+        info!("Batch of pool {}: {}", pool_address, serde_json::to_string(&report).unwrap_or_default());
+
+        let ids: HashMap<TradeId, usize> = legs.iter().copied().collect();
+        let deferred = processor.take_deferred();
+        self.resubmit(batch, deferred.iter().map(|(id, trade)| (ids[id], trade.as_ref())));
+
+        let plan = processor.plan();
+        let planned: Vec<(usize, &dyn ITrade, Quote)> = plan
+            .ids()
+            .zip(plan.iter())
+            .zip(processor.quotes(p_0))
+            .map(|((id, trade), quote)| (ids[&id], trade.as_ref(), quote))
+            .collect();
+
+        let submissions = match self.v4_settlement {
+            Some(settlement) => {
+                let key = self.pool_fetcher.pool_key(pool_address).expect("a V4 pool has a key");
+                self.settlement_transactions(settlement, &key, batch, &processor, &legs, &planned, &report.fills, p_0)
+            }
+            None => self.router_transactions(batch, &planned),
+        };
+        self.submit(batch, &[pool_address], submissions).await;
+    }
+
+    // Orders the legs of linked pools jointly and submits them in that order, the trades of a pool tagged with its index
+    // in the group and the legs of every path kept together. Trades which would revert are deferred, the others
+    // executed one after the other.
+    // Returns false, leaving the legs untouched, if a pool of the group cannot be read
+    // NOTE: the joint ordering is CLVR's, the strategies and netting of the pools do not apply to it
+    async fn execute_group(&self, batch: &Batch, group: &[Address]) -> bool {
+        let mut models = Vec::new();
+        let mut p_0 = Vec::new();
+        for &pool_address in group {
+            let Some((model, pool_p_0)) = self.pool_state(pool_address).await else {
+                return false;
+            };
+            models.push(model);
+            p_0.push(pool_p_0);
+        }
+        let model = MultiPoolModel::new(models);
+
+        let mut omega: Omega<Trade> = Omega::new();
+        let mut ids: HashMap<TradeId, usize> = HashMap::new();
+        for leg in (0..batch.legs.len()).filter(|&leg| group.contains(&batch.legs[leg].pool)) {
+            let k = group.iter().position(|&pool| pool == batch.legs[leg].pool).unwrap();
+            ids.insert(omega.push(batch.legs[leg].trade.clone().with_pool(k)), leg);
+        }
+        let legs: HashMap<usize, TradeId> = ids.iter().map(|(&id, &leg)| (leg, id)).collect();
+        let paths: Vec<Vec<TradeId>> = batch
+            .paths
+            .iter()
+            .filter(|path| legs.contains_key(&path[0]))
+            .map(|path| path.iter().map(|leg| legs[leg]).collect())
+            .collect();
+
+        info!("Ordering {} trades jointly on pools {:?} at p_0 {:?}", omega.len(), group, p_0);
+        info!("Prices of the linked pools: {:?}", model.prices());
+        let weights = self.strategy_config.weights;
+        let fifo = joint_objective(&model, &p_0, &omega, &weights);
+        clvr_order_joint(&model, &p_0, &mut omega, &paths, &weights);
+        let (quotes, deferred) = enforce_joint_slippage(&model, &mut omega, &paths);
+        info!("Joint objective of pools {:?}: {} against {} in arrival order", group, joint_objective(&model, &p_0, &omega, &weights).to_f64(), fifo.to_f64());
+        self.resubmit(batch, deferred.iter().map(|(id, _)| (ids[id], &batch.legs[ids[id]].trade as &dyn ITrade)));
+
+        let planned: Vec<(usize, &dyn ITrade, Quote)> = omega
+            .ids()
+            .zip(omega.iter())
+            .zip(quotes)
+            .map(|((id, trade), quote)| (ids[&id], trade as &dyn ITrade, quote))
+            .collect();
+        let submissions = match self.v4_settlement {
+            Some(settlement) => {
+                let keys: Vec<PoolKey> = group.iter().map(|&pool| self.pool_fetcher.pool_key(pool).expect("a V4 pool has a key")).collect();
+                Self::execute_transactions(settlement, &keys, batch, &planned)
+            }
+            None => self.router_transactions(batch, &planned),
+        };
+        self.submit(batch, group, submissions).await;
+
+        true
+    }
+
+    // schedules the deferred legs of the batch, each with what is left of its trade, again for the next batch
+    fn resubmit<'a>(&self, batch: &Batch, deferred: impl IntoIterator<Item = (usize, &'a dyn ITrade)>) {
+        let resubmitted = batch.resubmit(deferred);
+        if !resubmitted.is_empty() {
+            info!("Deferring {} trades to the next batch", resubmitted.len());
+            self.scheduled_db.lock().unwrap().extend(resubmitted);
+        }
+    }
+
+    // Settlement call of a V4 batch: a sequential plan is executed swap by swap, otherwise the pool executes the
+    // strategy's swaps and every trade is paid its fill within the batch and its quote against the pool
+    #[allow(clippy::too_many_arguments)]
+    fn settlement_transactions(
+        &self,
+        settlement: Address,
+        key: &PoolKey,
+        batch: &Batch,
+        processor: &Processor,
+        legs: &[(TradeId, usize)],
+        planned: &[(usize, &dyn ITrade, Quote)],
+        fills: &[Fill],
+        p_0: U256,
+    ) -> Vec<Submission> {
+        if processor.sequential() {
+            return Self::execute_transactions(settlement, std::slice::from_ref(key), batch, planned);
+        }
+
+        // a trade neither matched nor planned is deferred
+        let (settled, payouts): (Vec<usize>, Vec<_>) = legs
+            .iter()
+            .filter_map(|&(id, leg)| {
+                let fill = fills.iter().find(|fill| fill.id == id && !fill.matched_in.is_zero());
+                let quote = planned.iter().find(|&&(planned, ..)| planned == leg).map(|(_, _, quote)| quote);
+                if fill.is_none() && quote.is_none() {
+                    return None;
+                }
+
+                let (matched_in, matched_out) = fill.map_or((U256::ZERO, U256::ZERO), |fill| (fill.matched_in, fill.matched_out));
+                let (pool_in, pool_out) = quote.map_or((U256::ZERO, U256::ZERO), |quote| (quote.amount_in, quote.amount_out));
+                let trade: &dyn ITrade = &batch.legs[leg].trade;
+                let owner = batch.owner(leg);
+                Some((leg, (owner.from, owner.swap_params.recipient(), trade, Quote::new(trade, matched_in + pool_in, matched_out + pool_out))))
+            })
+            .unzip();
+        if payouts.is_empty() {
+            return Vec::new();
+        }
+
+        let pool_trades = processor.pool_trades(p_0);
+        let swaps: Vec<&dyn ITrade> = pool_trades.iter().map(|trade| trade as &dyn ITrade).collect();
+        vec![Submission { to: settlement, calldata: settle_calldata(key, &swaps, &payouts), calls: vec![settled] }]
+    }
+
+    // settlement call executing a V4 batch swap by swap in order, keys[k] being the key of the pool of the trades
+    // tagged with pool k
+    fn execute_transactions(settlement: Address, keys: &[PoolKey], batch: &Batch, planned: &[(usize, &dyn ITrade, Quote)]) -> Vec<Submission> {
+        if planned.is_empty() {
+            return Vec::new();
+        }
+
+        let swaps: Vec<(&PoolKey, Address, Address, &dyn ITrade)> = planned
+            .iter()
+            .map(|&(leg, trade, _)| (&keys[trade.get_pool()], batch.owner(leg).from, batch.owner(leg).swap_params.recipient(), trade))
+            .collect();
+        let legs = planned.iter().map(|&(leg, ..)| leg).collect();
+        vec![Submission { to: settlement, calldata: execute_calldata(&swaps), calls: vec![legs] }]
+    }
+
+    // Router contract call executing a V2, V3 or Curve batch in the order of the plan: each trade pays up to its amount
+    // in, which its router spends to swap for the trade's recipient, a Curve pool being its own router, and is refunded
+    // what the swap did not spend. A Curve pool swaps exactly the quoted input of an exact output trade for at least
+    // the output. A path is swapped at once by the router at its first leg
+    fn router_transactions(&self, batch: &Batch, planned: &[(usize, &dyn ITrade, Quote)]) -> Vec<Submission> {
+        let (mut calls, mut legs) = (Vec::new(), Vec::new());
+        for &(leg, trade, ref quote) in planned {
+            if let Some(path) = batch.path_of(leg) {
+                if path[0] == leg {
+                    calls.push(self.path_call(batch, path));
+                    legs.push(path.to_vec());
+                }
+                continue;
+            }
+
+            let owner = batch.owner(leg);
+            let hop = batch.legs[leg].hop();
+            let (recipient, deadline) = (owner.swap_params.recipient(), owner.swap_params.deadline());
+            let (target, data) = match (self.v2_router, self.v3_router) {
+                (Some(router), _) => (router, swap_router_v2::calldata(trade, batch.legs[leg].tokens, recipient, deadline)),
+                (None, Some(router)) => (router, SwapParams::from_trade(&hop, trade, recipient, deadline).calldata()),
+                (None, None) => {
+                    let pool_address = batch.legs[leg].pool;
+                    let coins = self.pool_fetcher.coins(pool_address).expect("a Curve pool has coins");
+                    let (dx, min_dy) = match trade.get_amount_out() {
+                        None => (trade.get_amount_in(), trade.get_amount_out_minimum()),
+                        Some(amount_out) => (quote.amount_in, amount_out),
+                    };
+                    (pool_address, curve::exchange_calldata(coins, hop.token_in, dx, min_dy, recipient))
+                }
+            };
+
+            calls.push(Call { from: owner.from, tokenIn: hop.token_in, amountIn: trade.get_amount_in(), target, data });
+            legs.push(vec![leg]);
+        }
+        if calls.is_empty() {
+            return Vec::new();
+        }
+
+        let router = self.clvr_router.expect("batches not settled by the settlement contract are executed by the router contract");
+        vec![Submission { to: router, calldata: clvr_router::execute_calldata(calls), calls: legs }]
+    }
+
+    // router contract call of a multi-hop trade: it pays up to its input, which the router swaps through the whole path
+    // for the trade's recipient, checking the minimum output at the end
+    fn path_call(&self, batch: &Batch, path: &[usize]) -> Call {
+        let router = self.v2_router.or(self.v3_router).expect("multi-hop trades are only executed through a router");
+        let owner = batch.owner(path[0]);
+        let amount = batch.legs[path[0]].trade.get_amount_in();
+        let data = match self.v2_router {
+            Some(_) => {
+                let (recipient, deadline) = (owner.swap_params.recipient(), owner.swap_params.deadline());
+                let amount_out_minimum = batch.legs[path[path.len() - 1]].trade.get_amount_out_minimum();
+                swap_router_v2::path_calldata(&owner.swap_params.hops(), amount, amount_out_minimum, recipient, deadline)
+            }
+            None => owner.swap_params.calldata(),
+        };
+
+        Call { from: owner.from, tokenIn: owner.swap_params.token_in(), amountIn: amount, target: router, data }
+    }
+
+    // Sends the transactions of the batch of pools in order, each once the previous one is mined. The legs of a call
+    // which fails, of a transaction which reverts and of the transactions not sent after a failure are scheduled again
+    // for the next batch. The legs of a transaction whose outcome is unknown may have executed, they are only reported
+    async fn submit(&self, batch: &Batch, pools: &[Address], submissions: Vec<Submission>) {
+        let mut failed: Vec<usize> = Vec::new();
+        let mut submissions = submissions.into_iter();
+        for Submission { to, calldata, calls } in submissions.by_ref() {
+            let receipt = match self.sender.send_transaction(transaction(to, calldata)).await {
+                Ok(pending) => pending.get_receipt().await,
+                Err(e) => {
+                    error!("Failed to send a transaction of the batch of pools {:?}: {}", pools, e);
+                    failed.extend(calls.into_iter().flatten());
+                    break;
+                }
+            };
+
+            match receipt {
+                Ok(receipt) if receipt.status() => {
+                    let failed_calls = clvr_router::failed_calls(to, receipt.inner.logs().iter().map(|log| &log.inner));
+                    info!("Executed {} on pools {:?}, {} of its {} calls failed", receipt.transaction_hash, pools, failed_calls.len(), calls.len());
+                    failed.extend(failed_calls.into_iter().filter_map(|i| calls.get(i)).flatten());
+                }
+                Ok(receipt) => {
+                    error!("Transaction {} of the batch of pools {:?} reverted", receipt.transaction_hash, pools);
+                    failed.extend(calls.into_iter().flatten());
+                }
+                Err(e) => {
+                    let legs: Vec<usize> = calls.into_iter().flatten().collect();
+                    error!("Failed to get the outcome of a transaction of the batch of pools {:?}, legs {:?} are left as failed: {}", pools, legs, e);
+                    break;
+                }
+            }
+        }
+
+        failed.extend(submissions.flat_map(|submission| submission.calls).flatten());
+        self.resubmit(batch, failed.into_iter().map(|leg| (leg, &batch.legs[leg].trade as &dyn ITrade)));
+    }
+
     pub async fn run(mut self) {
         loop {
+            if self.online {
+                self.intake().await;
+            }
+
             let current_block = self.provider.get_block_number().await.unwrap();
             if current_block > self.last_batch_block + self.block_period {
                 info!("Executing batch at block {}", current_block);
 
-                for trade in self.scheduled_db.lock().unwrap().iter() {
-                    let token_in = trade.swap_params.tokenIn;
-                    let token_out = trade.swap_params.tokenOut;
-                    let fee = trade.swap_params.fee;
+                // an online batch is what was taken in, trades arriving since are left for the next batch
+                let (batch, mut plans) = if self.online {
+                    let mut scheduled_db = self.scheduled_db.lock().unwrap();
+                    let late = scheduled_db.split_off(self.open.scheduled.len());
+                    *scheduled_db = late;
+                    self.quote_book.lock().unwrap().clear();
+                    (std::mem::take(&mut self.open), std::mem::take(&mut self.plans))
+                } else {
+                    let mut batch = Batch::new(std::mem::take(&mut *self.scheduled_db.lock().unwrap()));
+                    self.decompose(&mut batch, 0).await;
+                    (batch, HashMap::new())
+                };
+                let mut pools = batch.pools();
+
+                // trades of linked pools and of the pools of a path are ordered jointly, a group falls back to ordering
+                // each of its pools on its own if it cannot be read, its paths being scheduled again
+                for group in batch.groups(&self.linked_pools) {
+                    pools.retain(|pool| !group.contains(pool));
+
+                    if !self.execute_group(&batch, &group).await {
+                        let paths = (0..batch.legs.len()).filter(|&leg| group.contains(&batch.legs[leg].pool) && batch.path_of(leg).is_some());
+                        self.resubmit(&batch, paths.map(|leg| (leg, &batch.legs[leg].trade as &dyn ITrade)));
+                        for pool_address in group {
+                            self.execute_pool(&batch, pool_address, plans.remove(&pool_address)).await;
+                        }
+                    }
+                }
 
-                    let pool_address = self.pool_fetcher.get_pool_address(self.provider.clone(), token_in, token_out, fee);
-                    info!("Pool address: {}", pool_address);
+                for pool_address in pools {
+                    self.execute_pool(&batch, pool_address, plans.remove(&pool_address)).await;
                 }
                 self.last_batch_block = current_block;
             }
 
-            // sleep if the current block is not the predecessor of the next batch block
+            // sleep if the current block is not the predecessor of the next batch block, a submitted trade wakes the
+            // executor so that it is planned at once
             if current_block - self.last_batch_block <= self.block_period - 2 {
                 info!("Current block: {}", current_block);
-                sleep(Duration::from_millis(5000)).await;
+                let _ = timeout(Duration::from_millis(5000), self.arrivals.notified()).await;
             }
         }
     }
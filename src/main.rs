@@ -1,8 +1,8 @@
-use std::{fs, sync::{Arc, Mutex}};
+use std::{collections::HashMap, fs, sync::{Arc, Mutex}};
 
 use actix_web::{web, App, HttpServer};
 use log4rs;
-use server::handlers::ScheduledDatabase;
+use server::handlers::{Arrivals, QuoteBook, ScheduledDatabase};
 use log::error;
 
 mod clvr;
@@ -10,6 +10,7 @@ mod trades;
 mod server;
 mod executor;
 mod pool_fetcher;
+mod reference_price;
 
 const PORT: u16 = 8080;
 
@@ -60,9 +61,11 @@ async fn main() -> Result<(), std::io::Error> {
 
     // create the database
     let scheduled_db: ScheduledDatabase = Arc::new(Mutex::new(Vec::new()));
+    let quote_book: QuoteBook = Arc::new(Mutex::new(HashMap::new()));
+    let arrivals: Arrivals = Arc::new(Default::default());
 
     // start the process that waits and submits trades
-    let executor = executor::Executor::new(scheduled_db.clone());
+    let executor = executor::Executor::new(scheduled_db.clone(), quote_book.clone(), arrivals.clone());
     tokio::spawn(executor.run());
 
     // expose the api
@@ -70,8 +73,11 @@ async fn main() -> Result<(), std::io::Error> {
         let app_data = web::Data::new(scheduled_db.clone());
         App::new()
             .app_data(app_data)
+            .app_data(web::Data::new(quote_book.clone()))
+            .app_data(web::Data::new(arrivals.clone()))
             .service(server::handlers::num_trades)
             .service(server::handlers::submit_trade)
+            .service(server::handlers::quotes)
     })
     .bind(("127.0.0.1", PORT))?
     .workers(2)
@@ -0,0 +1,96 @@
+use alloy::{primitives::{aliases::U24, Address, Bytes, U256}, providers::RootProvider, sol, sol_types::SolCall};
+use async_trait::async_trait;
+use eyre::eyre;
+use crate::clvr::model::Model;
+use crate::clvr::model::stable_swap_model::StableSwapModel;
+use crate::executor::QueryTransport;
+use super::{v3::default_decimals, PoolFetcher};
+use std::collections::HashMap;
+use ICurvePool::exchangeCall;
+
+// fees of a Curve pool are expressed with 10 decimals, 1e10 being 100%
+const CURVE_FEE_DENOMINATOR: u64 = 10_000_000_000;
+
+sol! {
+    #[sol(rpc)]
+    interface ICurvePool {
+        function balances(uint256 i) external view returns (uint256);
+        function A() external view returns (uint256);
+        function fee() external view returns (uint256);
+        function exchange(int128 i, int128 j, uint256 dx, uint256 min_dy, address receiver) external returns (uint256);
+    }
+}
+
+// Reads configured Curve StableSwap pools of two coins, modelled by StableSwapModel. A pool has a single fee of its
+// own, whatever the fee tier asked for, and executes its swaps itself
+pub struct CurvePoolFetcher {
+    pools: HashMap<Address, (Address, Address)>, // coins of every pool, in the pool's order
+    decimals_map: HashMap<Address, u8>,
+}
+
+// fee of a Curve pool in hundredths of a bip
+fn fee_tier(fee: U256) -> U24 {
+    U24::from(fee * U256::from(1_000_000) / U256::from(CURVE_FEE_DENOMINATOR))
+}
+
+// Pool call swapping dx of token_in for at least min_dy of the other coin of the pool, delivered to receiver
+pub fn exchange_calldata((coin0, _): (Address, Address), token_in: Address, dx: U256, min_dy: U256, receiver: Address) -> Bytes {
+    let (i, j) = if token_in == coin0 { (0, 1) } else { (1, 0) };
+
+    exchangeCall { i, j, dx, min_dy, receiver }.abi_encode().into()
+}
+
+impl CurvePoolFetcher {
+    pub fn new(pools: HashMap<Address, (Address, Address)>) -> Self {
+        CurvePoolFetcher { pools, decimals_map: default_decimals() }
+    }
+
+    // configured pool of the pair, whatever the order of its coins
+    fn pool_of(&self, token_x: Address, token_y: Address) -> Option<Address> {
+        self.pools
+            .iter()
+            .find(|(_, &coins)| coins == (token_x, token_y) || coins == (token_y, token_x))
+            .map(|(&pool_address, _)| pool_address)
+    }
+}
+
+#[async_trait]
+impl PoolFetcher for CurvePoolFetcher {
+    // the configured pool of the pair, the zero address if there is none
+    fn get_pool_address(&self, _: RootProvider<QueryTransport>, token_x: Address, token_y: Address, _: U24) -> Address {
+        self.pool_of(token_x, token_y).unwrap_or(Address::ZERO)
+    }
+
+    async fn get_pools(&self, provider: RootProvider<QueryTransport>, token_x: Address, token_y: Address) -> eyre::Result<Vec<(U24, Address)>> {
+        let Some(pool_address) = self.pool_of(token_x, token_y) else {
+            return Ok(Vec::new());
+        };
+        let fee = ICurvePool::new(pool_address, provider).fee().call().await?._0;
+
+        Ok(vec![(fee_tier(fee), pool_address)])
+    }
+
+    // reads the balances, A and fee of a pool, token x of the model is the coin with the lower address as for
+    // Uniswap pools
+    async fn get_model(&self, provider: RootProvider<QueryTransport>, pool_address: Address) -> eyre::Result<Box<dyn Model>> {
+        let (coin0, coin1) = *self.pools.get(&pool_address).ok_or_else(|| eyre!("{} is not a configured Curve pool", pool_address))?;
+        let pool = ICurvePool::new(pool_address, provider);
+
+        let balance0 = pool.balances(U256::ZERO).call().await?._0;
+        let balance1 = pool.balances(U256::from(1)).call().await?._0;
+        let amplification = pool.A().call().await?._0;
+        let fee = pool.fee().call().await?._0;
+
+        let (decimals0, decimals1) = (self.decimals_map[&coin0], self.decimals_map[&coin1]);
+        let model = if coin0 < coin1 {
+            StableSwapModel::new(balance0, balance1, amplification.to(), fee_tier(fee)).with_decimals(decimals0, decimals1)
+        } else {
+            StableSwapModel::new(balance1, balance0, amplification.to(), fee_tier(fee)).with_decimals(decimals1, decimals0)
+        };
+        Ok(Box::new(model))
+    }
+
+    fn coins(&self, pool_address: Address) -> Option<(Address, Address)> {
+        self.pools.get(&pool_address).copied()
+    }
+}
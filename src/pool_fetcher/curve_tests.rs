@@ -0,0 +1,31 @@
+use crate::pool_fetcher::curve::{exchange_calldata, CurvePoolFetcher, ICurvePool::exchangeCall};
+use crate::pool_fetcher::PoolFetcher;
+use alloy::primitives::{address, aliases::U24, Address, U256};
+use alloy::providers::ProviderBuilder;
+use alloy::sol_types::SolCall;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_curve_pools() {
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let usdt = address!("dac17f958d2ee523a2206206994597c13d831ec7");
+        let pool = Address::with_last_byte(1);
+        let fetcher = CurvePoolFetcher::new(HashMap::from([(pool, (usdt, usdc))]));
+        let provider = ProviderBuilder::new().on_http("http://localhost:8545".parse().unwrap());
+
+        // the pool of the pair whatever the order of the tokens, the fee tier is the pool's own
+        assert_eq!(fetcher.get_pool_address(provider.clone(), usdc, usdt, U24::from(500)), pool);
+        assert_eq!(fetcher.get_pool_address(provider.clone(), usdt, usdc, U24::ZERO), pool);
+        assert_eq!(fetcher.get_pool_address(provider, usdc, Address::with_last_byte(2), U24::ZERO), Address::ZERO);
+        assert_eq!(fetcher.coins(pool), Some((usdt, usdc)));
+
+        // coins are exchanged by their index in the pool
+        let call = exchangeCall::abi_decode(&exchange_calldata((usdt, usdc), usdc, U256::from(10), U256::from(9), pool), true).unwrap();
+        assert_eq!((call.i, call.j), (1, 0));
+        assert_eq!((call.dx, call.min_dy, call.receiver), (U256::from(10), U256::from(9), pool));
+    }
+}
@@ -1,7 +1,36 @@
 use alloy::{primitives::{aliases::U24, Address}, providers::RootProvider};
+use async_trait::async_trait;
+use crate::clvr::model::Model;
 use crate::executor::QueryTransport;
+use crate::server::v4_settlement::PoolKey;
+pub mod curve;
+pub mod v2;
 pub mod v3;
+pub mod v4;
 
-pub trait PoolFetcher: Send {
+#[cfg(test)]
+mod curve_tests;
+#[cfg(test)]
+mod v2_tests;
+#[cfg(test)]
+mod v4_tests;
+
+// standard fee tiers of Uniswap pools, in hundredths of a bip
+pub const FEE_TIERS: [u32; 4] = [100, 500, 3000, 10000];
+
+#[async_trait]
+pub trait PoolFetcher: Send + Sync {
     fn get_pool_address(&self, provider: RootProvider<QueryTransport>, token_x: Address, token_y: Address, fee: U24) -> Address;
+    // pools of the pair which exist, among the FEE_TIERS, with their fee
+    async fn get_pools(&self, provider: RootProvider<QueryTransport>, token_x: Address, token_y: Address) -> eyre::Result<Vec<(U24, Address)>>;
+    // reads the current state of a pool into a model of it, token x of the model is the pool's token0
+    async fn get_model(&self, provider: RootProvider<QueryTransport>, pool_address: Address) -> eyre::Result<Box<dyn Model>>;
+    // key of a V4 pool, None for pools with a contract of their own
+    fn pool_key(&self, _pool_address: Address) -> Option<PoolKey> {
+        None
+    }
+    // coins of a Curve pool in the pool's order, None for Uniswap pools
+    fn coins(&self, _pool_address: Address) -> Option<(Address, Address)> {
+        None
+    }
 }
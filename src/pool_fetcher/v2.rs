@@ -0,0 +1,74 @@
+use alloy::{primitives::{address, aliases::U24, b256, keccak256, Address, B256, U256}, providers::{Provider, RootProvider}, sol};
+use async_trait::async_trait;
+use crate::clvr::model::Model;
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::executor::QueryTransport;
+use super::{v3::default_decimals, PoolFetcher};
+use std::collections::HashMap;
+
+pub const UNISWAP_V2_FACTORY: Address = address!("5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f");
+pub const UNISWAP_V2_INIT_CODE_HASH: B256 = b256!("96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f");
+const V2_FEE: u32 = 3000; // every pair charges 0.3%
+
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV2Pair {
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast);
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+    }
+}
+
+// Reads the pairs of a V2 factory (or of a fork of it, with its own init code hash). A pair is a constant product pool,
+// modelled exactly by CLVRModel
+pub struct V2PoolFetcher {
+    factory: Address,
+    init_code_hash: B256,
+    decimals_map: HashMap<Address, u8>,
+}
+
+// address the factory deploys the pair of two tokens at, with CREATE2 salted by the sorted tokens
+pub fn pair_address(factory: Address, init_code_hash: B256, token_x: Address, token_y: Address) -> Address {
+    let (token0, token1) = if token_x < token_y { (token_x, token_y) } else { (token_y, token_x) };
+    let salt = keccak256([token0.as_slice(), token1.as_slice()].concat());
+
+    factory.create2(salt, init_code_hash)
+}
+
+impl V2PoolFetcher {
+    pub fn new(factory: Address, init_code_hash: B256) -> Self {
+        V2PoolFetcher { factory, init_code_hash, decimals_map: default_decimals() }
+    }
+}
+
+#[async_trait]
+impl PoolFetcher for V2PoolFetcher {
+    // a pair has no fee tier
+    fn get_pool_address(&self, _: RootProvider<QueryTransport>, token_x: Address, token_y: Address, _: U24) -> Address {
+        pair_address(self.factory, self.init_code_hash, token_x, token_y)
+    }
+
+    // the single pair of the tokens, once deployed
+    async fn get_pools(&self, provider: RootProvider<QueryTransport>, token_x: Address, token_y: Address) -> eyre::Result<Vec<(U24, Address)>> {
+        let pool_address = pair_address(self.factory, self.init_code_hash, token_x, token_y);
+        if provider.get_code_at(pool_address).await?.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![(U24::from(V2_FEE), pool_address)])
+    }
+
+    // reads the reserves of a pair, token x of the model is the pair's token0
+    async fn get_model(&self, provider: RootProvider<QueryTransport>, pool_address: Address) -> eyre::Result<Box<dyn Model>> {
+        let pair = IUniswapV2Pair::new(pool_address, provider);
+
+        let reserves = pair.getReserves().call().await?;
+        let token0 = pair.token0().call().await?._0;
+        let token1 = pair.token1().call().await?._0;
+
+        let model = CLVRModel::new(U256::from(reserves.reserve0), U256::from(reserves.reserve1))
+            .with_fee(U24::from(V2_FEE))
+            .with_decimals(self.decimals_map[&token0], self.decimals_map[&token1]);
+        Ok(Box::new(model))
+    }
+}
@@ -0,0 +1,18 @@
+use crate::pool_fetcher::v2::{pair_address, UNISWAP_V2_FACTORY, UNISWAP_V2_INIT_CODE_HASH};
+use alloy::primitives::address;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_address() {
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+
+        // the USDC/WETH pair of mainnet, whatever the order of the tokens
+        let pair = address!("B4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc");
+        assert_eq!(pair_address(UNISWAP_V2_FACTORY, UNISWAP_V2_INIT_CODE_HASH, usdc, weth), pair);
+        assert_eq!(pair_address(UNISWAP_V2_FACTORY, UNISWAP_V2_INIT_CODE_HASH, weth, usdc), pair);
+    }
+}
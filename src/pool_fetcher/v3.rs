@@ -1,8 +1,11 @@
-use alloy::{primitives::{aliases::U24, Address}, providers::RootProvider};
+use alloy::{primitives::{aliases::U24, Address}, providers::{Provider, RootProvider}, sol};
+use async_trait::async_trait;
+use crate::clvr::model::Model;
 use once_cell::sync::Lazy;
+use crate::clvr::model::v3_model::V3Model;
 use crate::executor::QueryTransport;
-use super::PoolFetcher;
-use uniswap_v3_sdk::{entities::Pool, prelude::FeeAmount};
+use super::{PoolFetcher, FEE_TIERS};
+use uniswap_v3_sdk::{entities::Pool, prelude::{EphemeralTickDataProvider, FeeAmount}};
 use uniswap_sdk_core::entities::token::Token;
 use std::collections::HashMap;
 
@@ -10,28 +13,40 @@ const USDC: Lazy<Address> = Lazy::new(|| "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606
 const USDT: Lazy<Address> = Lazy::new(|| "0xdAC17F958D2ee523a2206206994597C13D831ec7".parse().unwrap());
 const WETH: Lazy<Address> = Lazy::new(|| "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C75677D".parse().unwrap());
 
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV3Pool {
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked);
+        function liquidity() external view returns (uint128);
+        function fee() external view returns (uint24);
+        function token0() external view returns (address);
+        function token1() external view returns (address);
+        function observe(uint32[] calldata secondsAgos) external view returns (int56[] memory tickCumulatives, uint160[] memory secondsPerLiquidityCumulativeX128s);
+    }
+}
+
 pub struct V3PoolFetcher {
     decimals_map: HashMap<Address, u8>,
 }
 
-impl V3PoolFetcher {
-    pub fn new() -> Self {
-        let decimals_map: HashMap<Address, u8> = HashMap::new();
-        let mut s = V3PoolFetcher {decimals_map};
+// decimals of the tokens the service knows of
+pub(super) fn default_decimals() -> HashMap<Address, u8> {
+    let mut decimals_map: HashMap<Address, u8> = HashMap::new();
+    decimals_map.insert(*USDC, 6);
+    decimals_map.insert(*USDT, 6);
+    decimals_map.insert(*WETH, 18);
 
-        s.default_decimals();
-
-        s
-    }
+    decimals_map
+}
 
-    fn default_decimals(&mut self) {
-        self.decimals_map.insert(*USDC, 6);
-        self.decimals_map.insert(*USDT, 6);
-        self.decimals_map.insert(*WETH, 18);
+impl V3PoolFetcher {
+    pub fn new() -> Self {
+        V3PoolFetcher { decimals_map: default_decimals() }
     }
 }
 
 
+#[async_trait]
 impl PoolFetcher for V3PoolFetcher {
     fn get_pool_address(&self, _: RootProvider<QueryTransport>, token_x: Address, token_y: Address, fee: U24) -> Address {
         let token_x = Token::new(crate::get_chain_id(), token_x, self.decimals_map[&token_x], None, None, None, None);
@@ -41,4 +56,33 @@ impl PoolFetcher for V3PoolFetcher {
 
         Pool::get_address(&token_x, &token_y, fee_amount, None, None)
     }
-}
\ No newline at end of file
+
+    // a pool exists once it is deployed at its CREATE2 address
+    async fn get_pools(&self, provider: RootProvider<QueryTransport>, token_x: Address, token_y: Address) -> eyre::Result<Vec<(U24, Address)>> {
+        let mut pools = Vec::new();
+        for fee in FEE_TIERS.map(U24::from) {
+            let pool_address = self.get_pool_address(provider.clone(), token_x, token_y, fee);
+            if !provider.get_code_at(pool_address).await?.is_empty() {
+                pools.push((fee, pool_address));
+            }
+        }
+
+        Ok(pools)
+    }
+
+    // reads the current state and the initialized ticks of a pool into a model of it, token x of the model is the pool's token0
+    async fn get_model(&self, provider: RootProvider<QueryTransport>, pool_address: Address) -> eyre::Result<Box<dyn Model>> {
+        let pool = IUniswapV3Pool::new(pool_address, provider.clone());
+
+        let slot0 = pool.slot0().call().await?;
+        let liquidity = pool.liquidity().call().await?._0;
+        let fee = pool.fee().call().await?._0;
+        let token0 = pool.token0().call().await?._0;
+        let token1 = pool.token1().call().await?._0;
+        let ticks = EphemeralTickDataProvider::<i32>::new(pool_address, provider, None, None, None).await?;
+
+        let model = V3Model::new(slot0.sqrtPriceX96, liquidity, slot0.tick.as_i32(), fee, ticks.ticks)
+            .with_decimals(self.decimals_map[&token0], self.decimals_map[&token1]);
+        Ok(Box::new(model))
+    }
+}
@@ -0,0 +1,105 @@
+use alloy::{primitives::{aliases::{I24, U24}, Address, U256}, providers::RootProvider, sol};
+use async_trait::async_trait;
+use crate::clvr::model::Model;
+use crate::clvr::model::v3_model::V3Model;
+use crate::executor::QueryTransport;
+use crate::server::v4_settlement::{pool_address, pool_id, pool_key, PoolKey};
+use super::{v3::default_decimals, PoolFetcher, FEE_TIERS};
+use uniswap_v3_sdk::prelude::Tick;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const TICK_WORDS: i32 = 2; // words of the tick bitmap read on either side of the current tick
+
+sol! {
+    #[sol(rpc)]
+    interface IStateView {
+        function getSlot0(bytes32 poolId) external view returns (uint160 sqrtPriceX96, int24 tick, uint24 protocolFee, uint24 lpFee);
+        function getLiquidity(bytes32 poolId) external view returns (uint128 liquidity);
+        function getTickBitmap(bytes32 poolId, int16 tick) external view returns (uint256 tickBitmap);
+        function getTickLiquidity(bytes32 poolId, int24 tick) external view returns (uint128 liquidityGross, int128 liquidityNet);
+    }
+}
+
+// Reads V4 pools of the PoolManager through its StateView lens. A V4 pool has the same concentrated liquidity as a V3
+// pool and is modelled as one at its current LP fee. The effect of hooks on swaps is not modelled
+pub struct V4PoolFetcher {
+    state_view: Address,
+    hooks: Address, // hooks of the pools the service trades in
+    decimals_map: HashMap<Address, u8>,
+    keys: Mutex<HashMap<Address, PoolKey>>, // key of every pool handed out by get_pool_address
+}
+
+// ticks initialized in word word_pos of a pool's tick bitmap
+pub fn initialized_ticks(word_pos: i32, bitmap: U256, tick_spacing: i32) -> Vec<i32> {
+    (0..256)
+        .filter(|&bit| bitmap.bit(bit))
+        .map(|bit| (word_pos * 256 + bit as i32) * tick_spacing)
+        .collect()
+}
+
+impl V4PoolFetcher {
+    pub fn new(state_view: Address, hooks: Address) -> Self {
+        let mut decimals_map = default_decimals();
+        decimals_map.insert(Address::ZERO, 18); // native ETH
+
+        V4PoolFetcher { state_view, hooks, decimals_map, keys: Mutex::new(HashMap::new()) }
+    }
+}
+
+#[async_trait]
+impl PoolFetcher for V4PoolFetcher {
+    fn get_pool_address(&self, _: RootProvider<QueryTransport>, token_x: Address, token_y: Address, fee: U24) -> Address {
+        let key = pool_key(token_x, token_y, fee.to::<u32>(), self.hooks);
+        let pool_address = pool_address(&key);
+        self.keys.lock().unwrap().insert(pool_address, key);
+
+        pool_address
+    }
+
+    // a pool exists once it is initialized with a price
+    async fn get_pools(&self, provider: RootProvider<QueryTransport>, token_x: Address, token_y: Address) -> eyre::Result<Vec<(U24, Address)>> {
+        let state_view = IStateView::new(self.state_view, provider.clone());
+
+        let mut pools = Vec::new();
+        for fee in FEE_TIERS.map(U24::from) {
+            let pool_address = self.get_pool_address(provider.clone(), token_x, token_y, fee);
+            let id = pool_id(&self.pool_key(pool_address).unwrap());
+            if !state_view.getSlot0(id).call().await?.sqrtPriceX96.is_zero() {
+                pools.push((fee, pool_address));
+            }
+        }
+
+        Ok(pools)
+    }
+
+    // reads the current state of a pool and the initialized ticks within TICK_WORDS bitmap words of its price,
+    // token x of the model is currency0
+    async fn get_model(&self, provider: RootProvider<QueryTransport>, pool_address: Address) -> eyre::Result<Box<dyn Model>> {
+        let key = self.pool_key(pool_address).ok_or_else(|| eyre::eyre!("Unknown V4 pool {}", pool_address))?;
+        let id = pool_id(&key);
+        let state_view = IStateView::new(self.state_view, provider);
+
+        let slot0 = state_view.getSlot0(id).call().await?;
+        let liquidity = state_view.getLiquidity(id).call().await?.liquidity;
+
+        let tick_spacing = key.tickSpacing.as_i32();
+        let word = slot0.tick.as_i32().div_euclid(tick_spacing) >> 8;
+        let mut ticks = Vec::new();
+        for word_pos in word - TICK_WORDS..word + TICK_WORDS + 1 {
+            let bitmap = state_view.getTickBitmap(id, word_pos as i16).call().await?.tickBitmap;
+            for index in initialized_ticks(word_pos, bitmap, tick_spacing) {
+                let tick = state_view.getTickLiquidity(id, I24::try_from(index)?).call().await?;
+                ticks.push(Tick::new(index, tick.liquidityGross, tick.liquidityNet));
+            }
+        }
+
+        let model = V3Model::new(slot0.sqrtPriceX96, liquidity, slot0.tick.as_i32(), slot0.lpFee, ticks)
+            .with_decimals(self.decimals_map[&key.currency0], self.decimals_map[&key.currency1]);
+        Ok(Box::new(model))
+    }
+
+    fn pool_key(&self, pool_address: Address) -> Option<PoolKey> {
+        self.keys.lock().unwrap().get(&pool_address).cloned()
+    }
+}
@@ -0,0 +1,17 @@
+use crate::pool_fetcher::v4::initialized_ticks;
+use alloy::primitives::U256;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initialized_ticks() {
+        let bitmap = U256::from(1) | (U256::from(1) << 255);
+
+        // bit b of word w is the tick (w * 256 + b) * tick_spacing
+        assert_eq!(initialized_ticks(0, bitmap, 60), vec![0, 255 * 60]);
+        assert_eq!(initialized_ticks(-1, bitmap, 10), vec![-2560, -10]);
+        assert!(initialized_ticks(3, U256::ZERO, 1).is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+use alloy::{primitives::{Address, U256}, providers::RootProvider, sol};
+use async_trait::async_trait;
+use eyre::eyre;
+use crate::clvr::model::Model;
+use crate::executor::QueryTransport;
+use super::ReferencePrice;
+
+sol! {
+    #[sol(rpc)]
+    interface AggregatorV3Interface {
+        function decimals() external view returns (uint8);
+        function latestRoundData() external view returns (uint80 roundId, int256 answer, uint256 startedAt, uint256 updatedAt, uint80 answeredInRound);
+    }
+}
+
+// Latest answer of a Chainlink style aggregator quoting token0 in token1, or token1 in token0 when inverse
+pub struct Chainlink {
+    pub aggregator: Address,
+    pub inverse: bool,
+}
+
+#[async_trait]
+impl ReferencePrice for Chainlink {
+    fn name(&self) -> &'static str {
+        "chainlink"
+    }
+
+    async fn price(&self, provider: RootProvider<QueryTransport>, _: Address, _: &dyn Model) -> eyre::Result<U256> {
+        let aggregator = AggregatorV3Interface::new(self.aggregator, provider);
+        let decimals = aggregator.decimals().call().await?._0;
+        let round = aggregator.latestRoundData().call().await?;
+
+        if round.updatedAt.is_zero() || !round.answer.is_positive() {
+            return Err(eyre!("Aggregator {} has no valid answer", self.aggregator));
+        }
+
+        Ok(answer_to_price(round.answer.into_raw(), decimals, self.inverse))
+    }
+}
+
+// p_0 from a positive aggregator answer, a whole token price with the aggregator's decimals, inverted when inverse
+pub fn answer_to_price(answer: U256, decimals: u8, inverse: bool) -> U256 {
+    let scale = U256::from(10).pow(U256::from(decimals));
+    let wad = U256::from(10).pow(U256::from(18));
+    if inverse {
+        wad * scale / answer
+    } else {
+        answer * wad / scale
+    }
+}
@@ -0,0 +1,74 @@
+use alloy::{primitives::{Address, U256}, providers::RootProvider};
+use async_trait::async_trait;
+use crate::clvr::model::{Model, Omega};
+use crate::executor::QueryTransport;
+use crate::trades::implementation::Trade;
+use self::chainlink::Chainlink;
+use self::v3::Twap;
+pub mod chainlink;
+pub mod v3;
+
+#[cfg(test)]
+mod reference_price_tests;
+
+// Source of the reference price p_0 a batch is ordered against
+#[async_trait]
+pub trait ReferencePrice: Send + Sync {
+    fn name(&self) -> &'static str;
+    // price of a whole token0 of the pool in whole tokens1, scaled by 10 ** 18 (see Model::P), given the model of its
+    // current state
+    async fn price(&self, provider: RootProvider<QueryTransport>, pool: Address, model: &dyn Model) -> eyre::Result<U256>;
+    // whether the price is read from the oracle of a V3 pool, which V2 pairs and V4 pools do not have
+    fn requires_v3_oracle(&self) -> bool {
+        false
+    }
+}
+
+// Current price of the pool, from the model the pool fetcher read of it: slot0 of a V3 pool, getSlot0 of a V4 pool
+// through the StateView, the reserves of a V2 pair
+pub struct Spot;
+
+#[async_trait]
+impl ReferencePrice for Spot {
+    fn name(&self) -> &'static str {
+        "spot"
+    }
+
+    async fn price(&self, _: RootProvider<QueryTransport>, _: Address, model: &dyn Model) -> eyre::Result<U256> {
+        Ok(model.P(&Omega::<Trade>::new(), 0))
+    }
+}
+
+// Constant p_0, for tests and manual overrides
+pub struct Fixed(pub U256);
+
+#[async_trait]
+impl ReferencePrice for Fixed {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    async fn price(&self, _: RootProvider<QueryTransport>, _: Address, _: &dyn Model) -> eyre::Result<U256> {
+        Ok(self.0)
+    }
+}
+
+// Resolves a reference price from configuration: spot, twap:<seconds>, chainlink:<aggregator>[:inverse] or fixed:<p_0>
+pub fn from_config(config: &str) -> Option<Box<dyn ReferencePrice>> {
+    let mut parts = config.split(':');
+
+    match (parts.next()?, parts.next(), parts.next(), parts.next()) {
+        ("spot", None, None, None) => Some(Box::new(Spot)),
+        ("twap", Some(window), None, None) => Some(Box::new(Twap { window: window.parse().ok()? })),
+        ("chainlink", Some(aggregator), inverse, None) => {
+            let inverse = match inverse {
+                None => false,
+                Some("inverse") => true,
+                Some(_) => return None,
+            };
+            Some(Box::new(Chainlink { aggregator: aggregator.parse().ok()?, inverse }))
+        }
+        ("fixed", Some(price), None, None) => Some(Box::new(Fixed(U256::from_str_radix(price, 10).ok()?))),
+        _ => None,
+    }
+}
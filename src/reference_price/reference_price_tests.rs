@@ -0,0 +1,87 @@
+use crate::clvr::model::clvr_model::CLVRModel;
+use crate::clvr::model::v3_model::V3Model;
+use crate::executor::QueryTransport;
+use crate::reference_price::chainlink::answer_to_price;
+use crate::reference_price::v3::mean_tick_price;
+use crate::reference_price::{from_config, ReferencePrice, Spot};
+use alloy::primitives::{aliases::U24, Address, U160, U256};
+use alloy::providers::{ProviderBuilder, RootProvider};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_price_from_config() {
+        let names = ["spot", "twap:1800", "chainlink:0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419", "fixed:1000000000000000000"];
+        for config in names {
+            let reference_price = from_config(config).unwrap();
+            assert_eq!(reference_price.name(), config.split(':').next().unwrap());
+        }
+
+        assert!(from_config("chainlink:0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419:inverse").is_some());
+        assert!(from_config("chainlink:0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419:other").is_none());
+        assert!(from_config("twap").is_none());
+        assert!(from_config("twap:-1").is_none());
+        assert!(from_config("median").is_none());
+    }
+
+    fn to_f64(x: U256) -> f64 {
+        x.to_string().parse().unwrap()
+    }
+
+    #[test]
+    fn test_twap_mean_tick_price() {
+        let wad = U256::from(10).pow(U256::from(18));
+        assert_eq!(mean_tick_price(0, 1800, (18, 18)).unwrap(), wad);
+        // a whole token of 6 decimals at tick 0 is worth 10 ** -12 whole tokens of 18 decimals
+        assert_eq!(mean_tick_price(0, 1800, (6, 18)).unwrap(), U256::from(1_000_000));
+
+        // mean ticks of 1.5 and -1.5 are rounded down to 1 and -2
+        let price = |delta: i64| to_f64(mean_tick_price(delta, 2, (18, 18)).unwrap()) / 1e18;
+        assert!((price(3) - 1.0001).abs() < 1e-12);
+        assert!((price(-3) - 1.0001f64.powi(-2)).abs() < 1e-12);
+        assert!((price(-4) - 1.0001f64.powi(-2)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_chainlink_answer_to_price() {
+        // ETH / USD with 8 decimals at 3000
+        let answer = U256::from(3000) * U256::from(100_000_000);
+        let wad = U256::from(10).pow(U256::from(18));
+        assert_eq!(answer_to_price(answer, 8, false), U256::from(3000) * wad);
+        assert_eq!(answer_to_price(answer, 8, true), U256::from(333_333_333_333_333u64));
+
+        // an answer with 18 decimals is p_0 as is
+        assert_eq!(answer_to_price(wad / U256::from(2), 18, false), wad / U256::from(2));
+        assert_eq!(answer_to_price(wad / U256::from(2), 18, true), U256::from(2) * wad);
+    }
+
+    // the spot price is read from the model, the provider is never called
+    fn provider() -> RootProvider<QueryTransport> {
+        ProviderBuilder::new().on_http("http://localhost:8545".parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_spot_from_model() {
+        // a V4 pool is read through the StateView into a V3Model, at sqrtPriceX96 = 2^96 a whole token of 6 decimals
+        // is worth 10 ** -12 whole tokens of 18 decimals
+        let pool = V3Model::new(U160::from(1) << 96, 1_000_000, 0, U24::from(3000), Vec::new()).with_decimals(6, 18);
+        let p_0 = Spot.price(provider(), Address::ZERO, &pool).await.unwrap();
+        assert_eq!(p_0, U256::from(1_000_000));
+
+        assert!(!Spot.requires_v3_oracle());
+        assert!(from_config("twap:1800").unwrap().requires_v3_oracle());
+    }
+
+    #[tokio::test]
+    async fn test_spot_of_v2_pair() {
+        // a pair is read from getReserves into a CLVRModel: 2000 USDC (6 decimals) against 1 WETH, a whole USDC is
+        // worth 1 / 2000 WETH whatever the pair's fee
+        let reserve_usdc = U256::from(2000) * U256::from(1_000_000);
+        let reserve_weth = U256::from(10).pow(U256::from(18));
+        let pair = CLVRModel::new(reserve_usdc, reserve_weth).with_fee(U24::from(3000)).with_decimals(6, 18);
+        let p_0 = Spot.price(provider(), Address::ZERO, &pair).await.unwrap();
+        assert_eq!(p_0, U256::from(500_000_000_000_000u64));
+    }
+}
@@ -0,0 +1,50 @@
+use alloy::{primitives::{Address, U256}, providers::RootProvider};
+use async_trait::async_trait;
+use eyre::eyre;
+use uniswap_v3_sdk::prelude::{get_sqrt_ratio_at_tick, TickIndex};
+use crate::clvr::model::v3_model::sqrt_price_to_price;
+use crate::clvr::model::Model;
+use crate::executor::QueryTransport;
+use crate::pool_fetcher::v3::IUniswapV3Pool;
+use super::ReferencePrice;
+
+// Time weighted average price of the pool over the last window seconds, from the pool's oracle
+pub struct Twap {
+    pub window: u32,
+}
+
+#[async_trait]
+impl ReferencePrice for Twap {
+    fn name(&self) -> &'static str {
+        "twap"
+    }
+
+    async fn price(&self, provider: RootProvider<QueryTransport>, pool: Address, model: &dyn Model) -> eyre::Result<U256> {
+        if self.window == 0 {
+            return Err(eyre!("TWAP window must be positive"));
+        }
+
+        let observations = IUniswapV3Pool::new(pool, provider).observe(vec![self.window, 0]).call().await?;
+        let cumulatives = observations.tickCumulatives;
+        let delta = cumulatives[1].as_i64() - cumulatives[0].as_i64();
+
+        mean_tick_price(delta, self.window, model.decimals())
+    }
+
+    fn requires_v3_oracle(&self) -> bool {
+        true
+    }
+}
+
+// price at the arithmetic mean tick of a window, given the change of the tick cumulative over it. The mean tick is
+// rounded towards negative infinity, as in the periphery's OracleLibrary
+pub fn mean_tick_price(delta: i64, window: u32, decimals: (u8, u8)) -> eyre::Result<U256> {
+    let window = window as i64;
+    let mut mean_tick = delta / window;
+    if delta < 0 && delta % window != 0 {
+        mean_tick -= 1;
+    }
+
+    let sqrt_price_x96 = get_sqrt_ratio_at_tick((mean_tick as i32).to_i24())?;
+    Ok(sqrt_price_to_price(sqrt_price_x96, decimals))
+}
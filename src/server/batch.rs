@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+
+use alloy::primitives::Address;
+use crate::clvr::model::Omega;
+use crate::server::handlers_types::ScheduledTrade;
+use crate::server::swap_router_v3::SwapParams;
+use crate::trades::implementation::Trade;
+use crate::trades::path::Hop;
+use crate::trades::{ITrade, TradeDirection};
+
+// Part of a scheduled trade executed against a single pool: the trade itself, a part of it routed to a fee tier, or a
+// hop of its path
+pub struct Leg {
+    pub scheduled: usize, // index of the scheduled trade in the batch
+    pub pool: Address,
+    pub tokens: (Address, Address), // token0 and token1 of the pool
+    pub trade: Trade,
+}
+
+impl Leg {
+    // the leg as a swap of the token it sells for the token it buys
+    pub fn hop(&self) -> Hop {
+        let (token0, token1) = self.tokens;
+        let (token_in, token_out) = match self.trade.get_direction() {
+            TradeDirection::Sell => (token0, token1),
+            TradeDirection::Buy => (token1, token0),
+        };
+
+        Hop { token_in, token_out, fee: self.trade.get_fee() }
+    }
+}
+
+// Trades scheduled for a batch, decomposed into the legs executed against every pool
+#[derive(Default)]
+pub struct Batch {
+    pub scheduled: Vec<ScheduledTrade>,
+    pub legs: Vec<Leg>,
+    pub paths: Vec<Vec<usize>>, // legs of every multi-hop trade in path order, executed by a single router call
+}
+
+impl Batch {
+    pub fn new(scheduled: Vec<ScheduledTrade>) -> Self {
+        Batch { scheduled, ..Default::default() }
+    }
+
+    // adds a leg of the scheduled trade swapping through hop in pool, returns its index
+    pub fn add_leg(&mut self, scheduled: usize, pool: Address, hop: &Hop, trade: Trade) -> usize {
+        let tokens = if hop.token_in < hop.token_out { (hop.token_in, hop.token_out) } else { (hop.token_out, hop.token_in) };
+        self.legs.push(Leg { scheduled, pool, tokens, trade });
+        self.legs.len() - 1
+    }
+
+    // registers the legs of a multi-hop trade, given in path order
+    pub fn add_path(&mut self, legs: Vec<usize>) {
+        self.paths.push(legs);
+    }
+
+    // legs of the path a leg is part of, None for a leg of a single pool trade
+    pub fn path_of(&self, leg: usize) -> Option<&[usize]> {
+        self.paths.iter().find(|path| path.contains(&leg)).map(|path| path.as_slice())
+    }
+
+    // who submitted the trade of a leg
+    pub fn owner(&self, leg: usize) -> &ScheduledTrade {
+        &self.scheduled[self.legs[leg].scheduled]
+    }
+
+    // pools of the batch in the order of their first leg
+    pub fn pools(&self) -> Vec<Address> {
+        let mut pools = Vec::new();
+        for leg in &self.legs {
+            if !pools.contains(&leg.pool) {
+                pools.push(leg.pool);
+            }
+        }
+
+        pools
+    }
+
+    // legs executed in pool, in arrival order
+    pub fn legs_of(&self, pool: Address) -> Vec<usize> {
+        (0..self.legs.len()).filter(|&leg| self.legs[leg].pool == pool).collect()
+    }
+
+    // Pools of the batch whose legs are ordered jointly, each group in the order of the pools: linked pools with legs
+    // in at least two of them, merged with the pools of every path
+    pub fn groups(&self, linked: &[Vec<Address>]) -> Vec<Vec<Address>> {
+        let pools = self.pools();
+        let linked = linked.iter().map(|group| group.iter().copied().filter(|pool| pools.contains(pool)).collect::<Vec<_>>());
+        let paths = self.paths.iter().map(|path| path.iter().map(|&leg| self.legs[leg].pool).collect::<Vec<_>>());
+
+        let mut groups: Vec<Vec<Address>> = Vec::new();
+        for mut merged in linked.chain(paths) {
+            groups.retain(|group| {
+                if !group.iter().any(|pool| merged.contains(pool)) {
+                    return true;
+                }
+                merged.extend(group);
+                false
+            });
+            groups.push(merged);
+        }
+
+        groups
+            .into_iter()
+            .map(|group| pools.iter().copied().filter(|pool| group.contains(pool)).collect::<Vec<_>>())
+            .filter(|group| group.len() > 1)
+            .collect()
+    }
+
+    // trades pending in every pool, so that a trade can be routed on top of them
+    pub fn pending(&self) -> HashMap<Address, Omega> {
+        let mut pending: HashMap<Address, Omega> = HashMap::new();
+        for leg in &self.legs {
+            pending.entry(leg.pool).or_default().push(Box::new(leg.trade.clone()));
+        }
+
+        pending
+    }
+
+    // Trades to schedule again for the legs left out of the batch, each given with what is left of its trade. A trade
+    // whose only leg is left out untouched is scheduled as submitted, otherwise what is left of the leg is scheduled
+    // as a single pool swap in its pool. A path is executed as a whole, a multi-hop trade is scheduled as submitted
+    // once whichever of its legs are left out
+    pub fn resubmit<'a>(&self, deferred: impl IntoIterator<Item = (usize, &'a dyn ITrade)>) -> Vec<ScheduledTrade> {
+        let mut paths = HashSet::new();
+        deferred
+            .into_iter()
+            .filter(|&(leg, _)| self.path_of(leg).is_none() || paths.insert(self.legs[leg].scheduled))
+            .map(|(leg, trade)| {
+                let owner = self.owner(leg);
+                if self.path_of(leg).is_some() {
+                    return owner.clone();
+                }
+
+                let single_leg = self.legs.iter().filter(|other| other.scheduled == self.legs[leg].scheduled).count() == 1;
+                if single_leg && Trade::from(trade) == self.legs[leg].trade {
+                    return owner.clone();
+                }
+
+                let (recipient, deadline) = (owner.swap_params.recipient(), owner.swap_params.deadline());
+                ScheduledTrade {
+                    swap_params: SwapParams::from_trade(&self.legs[leg].hop(), trade, recipient, deadline),
+                    ..owner.clone()
+                }
+            })
+            .collect()
+    }
+}
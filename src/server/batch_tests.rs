@@ -0,0 +1,115 @@
+use crate::server::batch::Batch;
+use crate::server::handlers_types::ScheduledTrade;
+use crate::server::swap_router_v3::SwapParams;
+use crate::trades::implementation::Trade;
+use crate::trades::path::Hop;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{aliases::U24, Address, PrimitiveSignature, U256};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // exact input swap of amount_in through hop, scheduled by from
+    fn scheduled(from: Address, hop: &Hop, amount_in: u64) -> ScheduledTrade {
+        let trade = Trade::new(U256::from(amount_in), hop.direction()).with_fee(hop.fee);
+        ScheduledTrade {
+            from,
+            swap_params: SwapParams::from_trade(hop, &trade, from, U256::from(100)),
+            permit_msg: Vec::new(),
+            signature: PrimitiveSignature::new(U256::ZERO, U256::ZERO, false),
+        }
+    }
+
+    #[test]
+    fn test_batch_legs() {
+        let (token0, token1) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let (pool_a, pool_b) = (Address::with_last_byte(10), Address::with_last_byte(11));
+        let sell = Hop { token_in: token0, token_out: token1, fee: U24::from(500) };
+        let buy = Hop { token_in: token1, token_out: token0, fee: U24::from(3000) };
+        let (alice, bob) = (Address::with_last_byte(20), Address::with_last_byte(21));
+
+        let mut batch = Batch::new(vec![scheduled(alice, &sell, 10), scheduled(bob, &buy, 20)]);
+        let trade = |s: usize| batch.scheduled[s].swap_params.single_trade().unwrap();
+        let (first, second) = (trade(0), trade(1));
+        batch.add_leg(1, pool_b, &buy, second);
+        batch.add_leg(0, pool_a, &sell, first);
+        let part = Trade::new(U256::from(5), TradeDirection::Buy).with_fee(U24::from(500));
+        batch.add_leg(1, pool_a, &buy, part);
+
+        // pools come in the order of their first leg, legs in arrival order
+        assert_eq!(batch.pools(), vec![pool_b, pool_a]);
+        assert_eq!(batch.legs_of(pool_a), vec![1, 2]);
+        assert_eq!(batch.owner(2).from, bob);
+
+        // a leg swaps the token it sells, whatever the order of the tokens of its hop
+        assert_eq!(batch.legs[0].tokens, (token0, token1));
+        assert_eq!(batch.legs[0].hop(), buy);
+        assert_eq!(batch.legs[2].hop().fee, U24::from(500));
+
+        let pending = batch.pending();
+        assert_eq!(pending[&pool_a].len(), 2);
+        assert_eq!(pending[&pool_b][1].get_amount_in(), U256::from(20));
+    }
+
+    #[test]
+    fn test_batch_resubmits_deferred() {
+        let (token0, token1) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let (pool_a, pool_b) = (Address::with_last_byte(10), Address::with_last_byte(11));
+        let sell = Hop { token_in: token0, token_out: token1, fee: U24::from(500) };
+        let (alice, bob) = (Address::with_last_byte(20), Address::with_last_byte(21));
+
+        let mut batch = Batch::new(vec![scheduled(alice, &sell, 10), scheduled(bob, &sell, 20)]);
+        let untouched = batch.scheduled[0].swap_params.single_trade().unwrap();
+        batch.add_leg(0, pool_a, &sell, untouched.clone());
+        // bob's trade is routed across two fee tiers
+        let part = |amount: u64, fee: u32| Trade::new(U256::from(amount), TradeDirection::Sell).with_fee(U24::from(fee));
+        batch.add_leg(1, pool_a, &sell, part(15, 500));
+        batch.add_leg(1, pool_b, &sell, part(5, 3000));
+
+        // an untouched trade is scheduled as submitted, a part or a residual as a swap in the pool of its leg
+        let residual = part(3, 3000);
+        let deferred: Vec<(usize, &dyn ITrade)> = vec![(0, &untouched), (2, &residual)];
+        let resubmitted = batch.resubmit(deferred);
+        assert_eq!(resubmitted.len(), 2);
+        assert_eq!(resubmitted[0].from, alice);
+        assert_eq!(resubmitted[0].swap_params.calldata(), batch.scheduled[0].swap_params.calldata());
+        assert_eq!(resubmitted[1].from, bob);
+        assert_eq!(resubmitted[1].swap_params.single_trade().unwrap(), residual);
+        assert_eq!(resubmitted[1].swap_params.hops()[0].fee, U24::from(3000));
+        assert_eq!(resubmitted[1].swap_params.recipient(), bob);
+    }
+    #[test]
+    fn test_batch_paths() {
+        let (a, b, c) = (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let (pool_ab, pool_bc, pool_ac, pool_cd) = (Address::with_last_byte(10), Address::with_last_byte(11), Address::with_last_byte(12), Address::with_last_byte(13));
+        let ab = Hop { token_in: a, token_out: b, fee: U24::from(500) };
+        let bc = Hop { token_in: b, token_out: c, fee: U24::from(500) };
+        let (alice, bob) = (Address::with_last_byte(20), Address::with_last_byte(21));
+
+        let mut batch = Batch::new(vec![scheduled(alice, &ab, 10), scheduled(bob, &ab, 20)]);
+        let leg = |amount: u64, hop: &Hop| Trade::new(U256::from(amount), hop.direction()).with_fee(hop.fee);
+        batch.add_leg(1, pool_ac, &ab, leg(20, &ab));
+        batch.add_leg(1, pool_cd, &ab, leg(20, &ab));
+        // alice's trade swaps a for c through b
+        let first = batch.add_leg(0, pool_ab, &ab, leg(10, &ab));
+        let second = batch.add_leg(0, pool_bc, &bc, leg(9, &bc));
+        batch.add_path(vec![first, second]);
+
+        assert_eq!(batch.path_of(second), Some(&[first, second][..]));
+        assert_eq!(batch.path_of(0), None);
+
+        // the pools of a path are merged with a group linked to one of them, a group needs two active pools
+        let linked = vec![vec![pool_ac, pool_bc], vec![pool_cd, Address::with_last_byte(14)]];
+        assert_eq!(batch.groups(&linked), vec![vec![pool_ac, pool_ab, pool_bc]]);
+        assert_eq!(batch.groups(&[]), vec![vec![pool_ab, pool_bc]]);
+
+        // a path is scheduled again once, as submitted, whichever of its legs are deferred
+        let (trade, residual) = (leg(9, &bc), leg(5, &ab));
+        let deferred: Vec<(usize, &dyn ITrade)> = vec![(second, &trade), (first, &trade), (1, &residual)];
+        let resubmitted = batch.resubmit(deferred);
+        assert_eq!(resubmitted.len(), 2);
+        assert_eq!(resubmitted[0].swap_params.calldata(), batch.scheduled[0].swap_params.calldata());
+        assert_eq!(resubmitted[1].from, bob);
+    }
+}
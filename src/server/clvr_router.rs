@@ -0,0 +1,41 @@
+use alloy::primitives::{Address, Bytes, Log};
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolEvent};
+use IClvrRouter::{executeCall, Call, Executed};
+
+sol! {
+    // Executes a batch on V2 pairs, V3 pools and Curve pools in one transaction (see contracts/ClvrRouter.sol). Each
+    // call pulls amountIn of tokenIn from its from, lets target spend it with data and refunds from what was not spent.
+    // A call which fails is refunded everything and the following calls still execute, Executed reporting the outcome
+    // of the call at index
+    #[sol(rpc)]
+    interface IClvrRouter {
+        #[derive(Debug)]
+        struct Call {
+            address from;
+            address tokenIn;
+            uint256 amountIn;
+            address target;
+            bytes data;
+        }
+
+        event Executed(uint256 indexed index, bool success, uint256 spent);
+
+        function execute(Call[] calldata calls) external;
+    }
+}
+
+// router call executing the calls in order
+pub fn execute_calldata(calls: Vec<Call>) -> Bytes {
+    executeCall { calls }.abi_encode().into()
+}
+
+// indices of the calls of an execution which failed, as reported by the Executed events of router among logs
+pub fn failed_calls<'a>(router: Address, logs: impl IntoIterator<Item = &'a Log>) -> Vec<usize> {
+    logs.into_iter()
+        .filter(|log| log.address == router)
+        .filter_map(|log| Executed::decode_log(log, true).ok())
+        .filter(|executed| !executed.success)
+        .map(|executed| executed.index.to::<usize>())
+        .collect()
+}
@@ -0,0 +1,44 @@
+use crate::server::clvr_router::{execute_calldata, failed_calls, IClvrRouter::{executeCall, Call, Executed}};
+use alloy::primitives::{Address, Bytes, Log, U256};
+use alloy::sol_types::{SolCall, SolEvent};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_calldata() {
+        let (from, token_in, target) = (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let calls = vec![
+            Call { from, tokenIn: token_in, amountIn: U256::from(10), target, data: Bytes::from(vec![1, 2]) },
+            Call { from: target, tokenIn: from, amountIn: U256::from(5), target: token_in, data: Bytes::new() },
+        ];
+
+        // the calls are encoded in execution order, each with what it may pay and its swap
+        let call = executeCall::abi_decode(&execute_calldata(calls), true).unwrap();
+        assert_eq!(call.calls.len(), 2);
+        assert_eq!((call.calls[0].from, call.calls[0].tokenIn, call.calls[0].target), (from, token_in, target));
+        assert_eq!(call.calls[0].amountIn, U256::from(10));
+        assert_eq!(call.calls[0].data, Bytes::from(vec![1, 2]));
+        assert_eq!((call.calls[1].from, call.calls[1].amountIn), (target, U256::from(5)));
+    }
+
+    #[test]
+    fn test_failed_calls() {
+        let (router, other) = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let executed = |address: Address, index: u64, success: bool| Log {
+            address,
+            data: Executed { index: U256::from(index), success, spent: U256::ZERO }.encode_log_data(),
+        };
+        let logs = vec![
+            executed(router, 0, true),
+            executed(router, 1, false),
+            executed(other, 2, false), // emitted by another contract
+            Log { address: router, data: Default::default() }, // not an Executed event
+            executed(router, 3, false),
+        ];
+
+        assert_eq!(failed_calls(router, &logs), vec![1, 3]);
+        assert!(failed_calls(other, &logs[..2]).is_empty());
+    }
+}
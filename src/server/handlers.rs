@@ -1,14 +1,26 @@
-use std::{str::FromStr, sync::{Arc, Mutex}};
+use std::{collections::HashMap, str::FromStr, sync::{Arc, Mutex}};
 use actix_web::{get, post, web, HttpResponse, Responder};
 use alloy::primitives::{Address, FixedBytes, PrimitiveSignature, U256};
 use log::{info, warn};
+use tokio::sync::Notify;
 use crate::server::handlers_types::*;
 use crate::server::{eip2612::verify_eip2612_signature};
 
 pub type ScheduledDatabase = Arc<Mutex<Vec<ScheduledTrade>>>;
+pub type QuoteBook = Arc<Mutex<HashMap<Address, Vec<PlannedQuote>>>>; // quotes of the current plan of every pool
+pub type Arrivals = Arc<Notify>; // wakes the executor when a trade is submitted
 
 const LOG_TARGET: &str = "server::handlers";
 
+// quotes of the trades planned so far in the open batch, published by the executor when CLVR_ONLINE is set
+#[get("/quotes")]
+pub async fn quotes(book: web::Data<QuoteBook>) -> impl Responder {
+    info!(target: LOG_TARGET, "quotes called");
+    HttpResponse::Ok().json(QuotesResponse {
+        quotes: book.lock().unwrap().values().flatten().cloned().collect(),
+    })
+}
+
 #[get("/num_trades")]
 pub async fn num_trades(db: web::Data<ScheduledDatabase>) -> impl Responder {
     info!(target: LOG_TARGET, "num_trades called");
@@ -34,9 +46,18 @@ MOCK REQUEST BODY:
     "permit_msg": "4f72bf4ece92162febe06cd70061da75707eb457f20c2a8ce580d424d5195049",
     "signature": "14e37d06070dca6bd1c14087f2857672c7bc385a5a09366de67c591b26a0e929442dbb42f8a66133aaff860a1f5afbbfb79b4a808ca3b7f662f90d7e68a265251b"
 }
+
+for an exact output swap, "amount_in" and "amount_out_minimum" are replaced by "amount_out" and "amount_in_maximum"
+
+"fee" may be omitted from a single pool swap, the service then routes it to the pool of the pair (fee tier 100, 500,
+3000 or 10000) with the best predicted output given the pending batch, or splits it across them (FEE_TIER_SPLIT_PARTS)
+
+for a multi-hop exact input swap, "token_in", "token_out", "fee" and "sqrt_price_limit_x96" are replaced by the hex
+encoded path token, fee, token, ..., e.g. USDC -> WETH at 0.05% -> USDT at 0.3%:
+    "path": "a0b86991c6218b36c1d19d4a2e9eb0ce3606eb480001f4c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2000bb8dac17f958d2ee523a2206206994597c13d831ec7"
  */
 #[post("/submit_trade")]
-pub async fn submit_trade(trade_request: web::Json<ScheduleRequest>, db: web::Data<ScheduledDatabase>,) -> impl Responder {
+pub async fn submit_trade(trade_request: web::Json<ScheduleRequest>, db: web::Data<ScheduledDatabase>, arrivals: web::Data<Arrivals>) -> impl Responder {
     info!(target: LOG_TARGET, "submit_trade called");
 
     let mut db = db.lock().unwrap();
@@ -67,9 +88,56 @@ pub async fn submit_trade(trade_request: web::Json<ScheduleRequest>, db: web::Da
         });
     }
     
+    // verify the path of a multi-hop swap
+    if !trade_request.swap_params.is_valid_path() {
+        warn!(target: LOG_TARGET, "Invalid path");
+        return HttpResponse::BadRequest().json(ScheduleResponse {
+            success: false,
+            message: "Invalid path".to_string(),
+        });
+    }
+
+    // verify the amounts
+    if !trade_request.swap_params.is_valid_amount() {
+        warn!(target: LOG_TARGET, "Invalid amount");
+        return HttpResponse::BadRequest().json(ScheduleResponse {
+            success: false,
+            message: "Invalid amount, it must not be zero".to_string(),
+        });
+    }
+
+    // verify the fee tiers, the models charge the fee out of the input
+    if !trade_request.swap_params.is_valid_fee() {
+        warn!(target: LOG_TARGET, "Invalid fee");
+        return HttpResponse::BadRequest().json(ScheduleResponse {
+            success: false,
+            message: "Invalid fee, it must not exceed 1000000 (100%)".to_string(),
+        });
+    }
+
+    // a V4 or Curve batch is executed swap by swap, each in a single pool
+    let version = std::env::var("UNISWAP_VERSION").unwrap_or("v3".to_string());
+    if trade_request.swap_params.is_multi_hop() && (version == "v4" || version == "curve") {
+        warn!(target: LOG_TARGET, "Multi-hop swap with UNISWAP_VERSION {}", version);
+        return HttpResponse::BadRequest().json(ScheduleResponse {
+            success: false,
+            message: format!("Multi-hop swaps are not available with UNISWAP_VERSION {}", version),
+        });
+    }
+
+    // the V2 router has no price limit, the swap would be modelled as limited and executed in full
+    if trade_request.swap_params.has_price_limit() && version == "v2" {
+        warn!(target: LOG_TARGET, "Price limit with UNISWAP_VERSION v2");
+        return HttpResponse::BadRequest().json(ScheduleResponse {
+            success: false,
+            message: "sqrt_price_limit_x96 must be 0 with UNISWAP_VERSION v2".to_string(),
+        });
+    }
+
     let scheduled_trade: ScheduledTrade = trade_request.into_inner().into();
     let scheduled_trade_clone = scheduled_trade.clone();
     db.push(scheduled_trade);
+    arrivals.notify_one();
 
     HttpResponse::Created().json(ScheduleResponse {
         success: true,
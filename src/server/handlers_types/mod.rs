@@ -1,10 +1,8 @@
 use std::str::FromStr;
 
-use alloy::{hex, primitives::{Address, PrimitiveSignature}};
+use alloy::{hex, primitives::{Address, PrimitiveSignature, U256}};
 use serde::{Deserialize, Serialize};
-use crate::server::swap_router_v3::ISwapRouter::ExactInputSingleParams;
-
-use super::swap_router_v3::ExactInputSingleParamsIntermediate;
+use super::swap_router_v3::{SwapParams, SwapParamsIntermediate};
 
 // API Types
 #[derive(Serialize, Deserialize)]
@@ -23,9 +21,30 @@ pub struct ScheduleRequest {
         uint160 sqrtPriceLimitX96;
     }
 
+    or, to buy a fixed amount of token_out,
+    struct ExactOutputSingleParams {
+        address tokenIn;
+        address tokenOut;
+        uint24 fee;
+        address recipient;
+        uint256 deadline;
+        uint256 amountOut;
+        uint256 amountInMaximum;
+        uint160 sqrtPriceLimitX96;
+    }
+
+    or, to swap through several pools,
+    struct ExactInputParams {
+        bytes path;
+        address recipient;
+        uint256 deadline;
+        uint256 amountIn;
+        uint256 amountOutMinimum;
+    }
+
     encoded as a json string
      */
-    pub swap_params: ExactInputSingleParamsIntermediate, 
+    pub swap_params: SwapParamsIntermediate, 
     pub permit_msg: String,
     pub signature: String,
 }
@@ -41,11 +60,26 @@ pub struct NumTradesResponse {
     pub num_trades: u64,
 }
 
+// What a trade of the current plan of a pool is quoted to pay and receive, in plan order
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlannedQuote {
+    pub pool: Address,
+    pub from: Address,
+    pub recipient: Address,
+    pub amount_in: U256,
+    pub amount_out: U256,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct QuotesResponse {
+    pub quotes: Vec<PlannedQuote>,
+}
+
 // Internal Types
 #[derive(Clone)]
 pub struct ScheduledTrade {
     pub from: Address,
-    pub swap_params: ExactInputSingleParams,
+    pub swap_params: SwapParams,
     pub permit_msg: Vec<u8>,
     pub signature: PrimitiveSignature,
 }
@@ -53,7 +87,7 @@ pub struct ScheduledTrade {
 impl From<ScheduleRequest> for ScheduledTrade {
     fn from(request: ScheduleRequest) -> Self {
         let from_address = Address::from_str(&request.from).unwrap();
-        let swap_params: ExactInputSingleParams = request.swap_params.into();
+        let swap_params: SwapParams = request.swap_params.into();
         let permit_msg: Vec<u8> = hex::decode(request.permit_msg).unwrap();
         let signature: PrimitiveSignature = PrimitiveSignature::from_str(&request.signature).unwrap();
         ScheduledTrade { from: from_address, swap_params, permit_msg, signature }
@@ -1,37 +1,177 @@
 
 use alloy::primitives::U256;
 
-use crate::clvr::model::{clvr_model::CLVRModel, Omega};
+use crate::clvr::algorithm::clvr_insert;
+use crate::clvr::metrics::{quotes, BatchMetrics, BatchReport, Quote};
+use crate::clvr::model::{Model, Omega, TradeId};
+use crate::clvr::netting::{net, Fill};
+use crate::clvr::slippage::enforce_slippage;
+use crate::clvr::strategy::OrderingStrategy;
+use crate::trades::implementation::Trade;
 use crate::trades::ITrade;
 
-mod swap_router_v3;
+pub mod batch;
+pub mod clvr_router;
+pub mod swap_router_v2;
+pub mod swap_router_v3;
+pub mod v4_settlement;
 pub mod handlers;
 pub mod tokens;
-mod handlers_types;
+pub mod handlers_types;
 mod eip2612;
 
+#[cfg(test)]
+mod batch_tests;
+#[cfg(test)]
+mod clvr_router_tests;
 #[cfg(test)]
 mod eip2612_tests;
+#[cfg(test)]
+mod swap_router_v2_tests;
+#[cfg(test)]
+mod v4_settlement_tests;
 
 // Processor is responsible for performing one instance of the algorithm. Called by executor. (TODO: probably rename these two)
 pub struct Processor {
     omega: Omega,
-    model: CLVRModel,
+    arrival: Vec<TradeId>, // trades of the batch in arrival order
+    model: Box<dyn Model>,
+    strategy: Box<dyn OrderingStrategy>,
+    deferred: Vec<(TradeId, Box<dyn ITrade>)>, // trades excluded from the last ordering, to be resubmitted with the next batch
+    netting: bool, // match opposite trades of the batch internally before ordering the residual
 }
 
 impl Processor {
-    pub fn new(reserve_y: U256, reserve_x: U256) -> Self {
+    pub fn new(model: Box<dyn Model>, strategy: Box<dyn OrderingStrategy>) -> Self {
         // create variables related to the algorithm
         let omega = Omega::new();
-        let model = CLVRModel::new(reserve_y, reserve_x);
 
         Self {
             omega,
+            arrival: Vec::new(),
             model,
+            strategy,
+            deferred: Vec::new(),
+            netting: false,
+        }
+    }
+
+    pub fn with_netting(mut self) -> Self {
+        self.netting = true;
+        self
+    }
+
+    // adds an arriving trade to the batch, returning its identity in the batch
+    pub fn add_trade(&mut self, trade: Box<dyn ITrade>) -> TradeId {
+        let id = self.omega.push(trade);
+        self.arrival.push(id);
+        id
+    }
+
+    // Inserts an arriving trade into the current plan at its best position, so that an ordering is ready when the
+    // batch closes (see order_plan)
+    pub fn insert_trade(&mut self, trade: Box<dyn ITrade>, p_0: U256) -> TradeId {
+        let p = clvr_insert(self.model.as_ref(), p_0, &mut self.omega, trade, &self.strategy.weights());
+        let id = self.omega.id(p);
+        self.arrival.push(id);
+        id
+    }
+
+    // the pool's current state, the plan is kept
+    pub fn update_model(&mut self, model: Box<dyn Model>) {
+        self.model = model;
+    }
+
+    // what each trade of the current plan pays and receives, in plan order
+    pub fn quotes(&self, p_0: U256) -> Vec<Quote> {
+        self.strategy.quotes(self.model.as_ref(), p_0, &self.omega)
+    }
+
+    // trades of the current plan in order, once ordered only those to execute against the pool
+    pub fn plan(&self) -> &Omega {
+        &self.omega
+    }
+
+    // whether the plan is executed trade by trade, otherwise the batch is settled at once: netted trades are paid
+    // their fill on top of their quote
+    pub fn sequential(&self) -> bool {
+        !self.netting && self.strategy.sequential()
+    }
+
+    // swaps the pool executes for the current plan
+    pub fn pool_trades(&self, p_0: U256) -> Vec<Trade> {
+        self.strategy.pool_trades(self.model.as_ref(), p_0, &self.omega)
+    }
+
+    // rearranges omega in the order of ids
+    fn reorder(&mut self, ids: &[TradeId]) {
+        let positions: Vec<usize> = ids.iter().map(|&id| self.omega.position(id).expect("unknown trade")).collect();
+        self.omega.reorder(&positions);
+    }
+
+    // metrics of the batch in arrival order, omega is left in plan order
+    fn fifo_metrics(&mut self, p_0: U256) -> BatchMetrics {
+        let plan: Vec<TradeId> = self.omega.ids().collect();
+        self.reorder(&self.arrival.clone());
+        let fifo_quotes = quotes(self.model.as_ref(), &self.omega);
+        let fifo = BatchMetrics::new(self.model.as_ref(), p_0, &self.omega, fifo_quotes, &self.strategy.weights());
+        self.reorder(&plan);
+
+        fifo
+    }
+
+    // nets the batch at the pool's spot price if enabled, returning the fills in arrival order
+    fn net(&mut self) -> Vec<Fill> {
+        if !self.netting {
+            return Vec::new();
+        }
+
+        let spot = self.model.P(&Omega::<Trade>::new(), 0);
+        let mut fills = net(spot, self.model.decimals(), &mut self.omega);
+        fills.sort_by_key(|fill| self.arrival.iter().position(|&id| id == fill.id));
+        fills
+    }
+
+    // excludes trades that would revert and reports the executed ordering against the arrival order
+    fn close(&mut self, p_0: U256, strategy: &str, fifo: BatchMetrics, fills: Vec<Fill>) -> BatchReport {
+        let weights = self.strategy.weights();
+        self.deferred = enforce_slippage(self.model.as_ref(), p_0, &mut self.omega, &weights);
+        let ordered_quotes = self.strategy.quotes(self.model.as_ref(), p_0, &self.omega);
+        let ordered = BatchMetrics::new(self.model.as_ref(), p_0, &self.omega, ordered_quotes, &weights);
+        self.arrival.clear();
+
+        BatchReport {
+            strategy: strategy.to_string(),
+            weights,
+            ordered,
+            fifo,
+            deferred: self.deferred.len(),
+            fills,
         }
     }
 
-    fn add_trade(&mut self, trade: Box<dyn ITrade>) {
-        self.omega.push(trade);
+    // orders the batch from scratch, optionally netted, excluding trades that would revert, and reports its metrics
+    // against the arrival order
+    pub fn order(&mut self, p_0: U256) -> BatchReport {
+        self.reorder(&self.arrival.clone()); // the strategy starts from the arrival order
+
+        let fifo = self.fifo_metrics(p_0);
+        let fills = self.net();
+        self.strategy.order(self.model.as_ref(), p_0, &mut self.omega);
+
+        self.close(p_0, self.strategy.name(), fifo, fills)
+    }
+
+    // closes the batch with the plan built by insert_trade instead of ordering it from scratch
+    pub fn order_plan(&mut self, p_0: U256) -> BatchReport {
+        let fifo = self.fifo_metrics(p_0);
+        let fills = self.net();
+
+        self.close(p_0, "online", fifo, fills)
+    }
+
+    // trades excluded from the last ordering with their ids, as added or, once netted, their residual
+    pub fn take_deferred(&mut self) -> Vec<(TradeId, Box<dyn ITrade>)> {
+        std::mem::take(&mut self.deferred)
     }
 }
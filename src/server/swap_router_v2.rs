@@ -0,0 +1,52 @@
+use alloy::{primitives::{Address, Bytes, U256}, sol, sol_types::SolCall};
+use crate::trades::path::Hop;
+use crate::trades::{ITrade, TradeDirection};
+use IUniswapV2Router02::{swapExactTokensForTokensCall, swapTokensForExactTokensCall};
+
+sol! {
+    #[sol(rpc)]
+    interface IUniswapV2Router02 {
+        function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] calldata path, address to, uint256 deadline) external returns (uint256[] memory amounts);
+        function swapTokensForExactTokens(uint256 amountOut, uint256 amountInMax, address[] calldata path, address to, uint256 deadline) external returns (uint256[] memory amounts);
+    }
+}
+
+// Router call executing trade on the pair of token0 and token1, token x being token0, delivering to recipient before
+// deadline. The trades of a batch are executed by consecutive calls in their order
+pub fn calldata(trade: &dyn ITrade, (token0, token1): (Address, Address), recipient: Address, deadline: U256) -> Bytes {
+    let path = match trade.get_direction() {
+        TradeDirection::Sell => vec![token0, token1],
+        TradeDirection::Buy => vec![token1, token0],
+    };
+
+    match trade.get_amount_out() {
+        None => swapExactTokensForTokensCall {
+            amountIn: trade.get_amount_in(),
+            amountOutMin: trade.get_amount_out_minimum(),
+            path,
+            to: recipient,
+            deadline,
+        }
+        .abi_encode()
+        .into(),
+        Some(amount_out) => swapTokensForExactTokensCall {
+            amountOut: amount_out,
+            amountInMax: trade.get_amount_in(),
+            path,
+            to: recipient,
+            deadline,
+        }
+        .abi_encode()
+        .into(),
+    }
+}
+
+// Router call swapping amount_in through every pair of hops at once, delivering at least amount_out_minimum of the
+// last token to recipient before deadline
+pub fn path_calldata(hops: &[Hop], amount_in: U256, amount_out_minimum: U256, recipient: Address, deadline: U256) -> Bytes {
+    let path = hops.iter().take(1).map(|hop| hop.token_in).chain(hops.iter().map(|hop| hop.token_out)).collect();
+
+    swapExactTokensForTokensCall { amountIn: amount_in, amountOutMin: amount_out_minimum, path, to: recipient, deadline }
+        .abi_encode()
+        .into()
+}
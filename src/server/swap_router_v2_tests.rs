@@ -0,0 +1,45 @@
+use crate::server::swap_router_v2::{calldata, path_calldata};
+use crate::server::swap_router_v2::IUniswapV2Router02::{swapExactTokensForTokensCall, swapTokensForExactTokensCall};
+use crate::trades::implementation::Trade;
+use crate::trades::path::Hop;
+use crate::trades::TradeDirection;
+use alloy::primitives::{aliases::U24, Address, U256};
+use alloy::sol_types::SolCall;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v2_calldata() {
+        let tokens = (Address::with_last_byte(1), Address::with_last_byte(2));
+        let recipient = Address::with_last_byte(3);
+
+        // selling token x swaps token0 for token1
+        let sell = Trade::new(U256::from(10), TradeDirection::Sell).with_amount_out_minimum(U256::from(9));
+        let call = swapExactTokensForTokensCall::abi_decode(&calldata(&sell, tokens, recipient, U256::from(100)), true).unwrap();
+        assert_eq!((call.amountIn, call.amountOutMin), (U256::from(10), U256::from(9)));
+        assert_eq!(call.path, vec![tokens.0, tokens.1]);
+        assert_eq!((call.to, call.deadline), (recipient, U256::from(100)));
+
+        let buy = Trade::new_exact_output(U256::from(10), U256::from(12), TradeDirection::Buy);
+        let call = swapTokensForExactTokensCall::abi_decode(&calldata(&buy, tokens, recipient, U256::from(100)), true).unwrap();
+        assert_eq!((call.amountOut, call.amountInMax), (U256::from(10), U256::from(12)));
+        assert_eq!(call.path, vec![tokens.1, tokens.0]);
+    }
+    #[test]
+    fn test_v2_path_calldata() {
+        let (a, b, c) = (Address::with_last_byte(1), Address::with_last_byte(2), Address::with_last_byte(3));
+        let hops = vec![
+            Hop { token_in: a, token_out: b, fee: U24::from(3000) },
+            Hop { token_in: b, token_out: c, fee: U24::from(3000) },
+        ];
+        let recipient = Address::with_last_byte(4);
+
+        // a path swaps through every token in order
+        let call = swapExactTokensForTokensCall::abi_decode(&path_calldata(&hops, U256::from(10), U256::from(9), recipient, U256::from(100)), true).unwrap();
+        assert_eq!((call.amountIn, call.amountOutMin), (U256::from(10), U256::from(9)));
+        assert_eq!(call.path, vec![a, b, c]);
+        assert_eq!((call.to, call.deadline), (recipient, U256::from(100)));
+    }
+}
@@ -1,8 +1,15 @@
 use std::str::FromStr;
 
-use alloy::{primitives::{aliases::U24, Address, U160, U256}, sol};
+use alloy::{hex, primitives::{aliases::U24, Address, Bytes, U160, U256}, sol, sol_types::SolCall};
 use serde::{Deserialize, Serialize};
-use ISwapRouter::ExactInputSingleParams;
+use ISwapRouter::{ExactInputParams, ExactInputSingleParams, ExactOutputSingleParams};
+use SwapRouterV3::{exactInputCall, exactInputSingleCall, exactOutputSingleCall};
+use crate::trades::implementation::Trade;
+use crate::trades::ITrade;
+use crate::trades::path::{decode_path, Hop};
+
+// fees are expressed in hundredths of a bip, a fee above 100% cannot be charged
+const MAX_FEE: u32 = 1_000_000;
 
 sol!(
     #[sol(rpc)]
@@ -26,7 +33,8 @@ pub struct ExactInputSingleParams {
 pub struct ExactInputSingleParamsIntermediate {
     pub token_in: String,
     pub token_out: String,
-    pub fee: U24,
+    #[serde(default)]
+    pub fee: U24, // 0 when omitted, the service then selects the fee tier
     pub recipient: String,
     pub deadline: U256,
     pub amount_in: U256,
@@ -34,6 +42,188 @@ pub struct ExactInputSingleParamsIntermediate {
     pub sqrt_price_limit_x96: U160,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ExactOutputSingleParamsIntermediate {
+    pub token_in: String,
+    pub token_out: String,
+    #[serde(default)]
+    pub fee: U24, // 0 when omitted, the service then selects the fee tier
+    pub recipient: String,
+    pub deadline: U256,
+    pub amount_out: U256,
+    pub amount_in_maximum: U256,
+    pub sqrt_price_limit_x96: U160,
+}
+
+// Multi-hop exact input swap, path is the hex encoded router path token, fee, token, fee, ..., token
+#[derive(Serialize, Deserialize)]
+pub struct ExactInputParamsIntermediate {
+    pub path: String,
+    pub recipient: String,
+    pub deadline: U256,
+    pub amount_in: U256,
+    pub amount_out_minimum: U256,
+}
+
+// Any kind of swap, told apart by their fields (amount_in or amount_out, path or tokens)
+// variants are named after the router functions
+#[allow(clippy::enum_variant_names)]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SwapParamsIntermediate {
+    ExactInputSingle(ExactInputSingleParamsIntermediate),
+    ExactOutputSingle(ExactOutputSingleParamsIntermediate),
+    ExactInput(ExactInputParamsIntermediate),
+}
+
+impl SwapParamsIntermediate {
+    // false for a path that is not hex or does not decode, single pool swaps are checked on conversion
+    pub fn is_valid_path(&self) -> bool {
+        match self {
+            SwapParamsIntermediate::ExactInput(params) => hex::decode(&params.path)
+                .ok()
+                .and_then(|path| decode_path(&path))
+                .is_some(),
+            _ => true,
+        }
+    }
+
+    // false if nothing is swapped, the models and the settlement divide by the amounts
+    pub fn is_valid_amount(&self) -> bool {
+        match self {
+            SwapParamsIntermediate::ExactInputSingle(params) => !params.amount_in.is_zero(),
+            SwapParamsIntermediate::ExactOutputSingle(params) => {
+                !params.amount_out.is_zero() && !params.amount_in_maximum.is_zero()
+            }
+            SwapParamsIntermediate::ExactInput(params) => !params.amount_in.is_zero(),
+        }
+    }
+
+    // false if the fee of a pool swapped in exceeds 100%, a malformed path is left to is_valid_path
+    pub fn is_valid_fee(&self) -> bool {
+        let fees = match self {
+            SwapParamsIntermediate::ExactInputSingle(params) => vec![params.fee],
+            SwapParamsIntermediate::ExactOutputSingle(params) => vec![params.fee],
+            SwapParamsIntermediate::ExactInput(params) => hex::decode(&params.path)
+                .ok()
+                .and_then(|path| decode_path(&path))
+                .map(|hops| hops.iter().map(|hop| hop.fee).collect())
+                .unwrap_or_default(),
+        };
+
+        fees.iter().all(|&fee| fee <= U24::from(MAX_FEE))
+    }
+
+    pub fn has_price_limit(&self) -> bool {
+        match self {
+            SwapParamsIntermediate::ExactInputSingle(params) => !params.sqrt_price_limit_x96.is_zero(),
+            SwapParamsIntermediate::ExactOutputSingle(params) => !params.sqrt_price_limit_x96.is_zero(),
+            SwapParamsIntermediate::ExactInput(_) => false,
+        }
+    }
+
+    pub fn is_multi_hop(&self) -> bool {
+        matches!(self, SwapParamsIntermediate::ExactInput(_))
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Clone, Debug)]
+pub enum SwapParams {
+    ExactInputSingle(ExactInputSingleParams),
+    ExactOutputSingle(ExactOutputSingleParams),
+    ExactInput(ExactInputParams),
+}
+
+impl SwapParams {
+    // pools the swap goes through in order, a single pool swap has one hop
+    pub fn hops(&self) -> Vec<Hop> {
+        match self {
+            SwapParams::ExactInputSingle(params) => vec![Hop { token_in: params.tokenIn, token_out: params.tokenOut, fee: params.fee }],
+            SwapParams::ExactOutputSingle(params) => vec![Hop { token_in: params.tokenIn, token_out: params.tokenOut, fee: params.fee }],
+            SwapParams::ExactInput(params) => decode_path(&params.path).expect("path must be validated on submission"),
+        }
+    }
+
+    pub fn recipient(&self) -> Address {
+        match self {
+            SwapParams::ExactInputSingle(params) => params.recipient,
+            SwapParams::ExactOutputSingle(params) => params.recipient,
+            SwapParams::ExactInput(params) => params.recipient,
+        }
+    }
+
+    pub fn deadline(&self) -> U256 {
+        match self {
+            SwapParams::ExactInputSingle(params) => params.deadline,
+            SwapParams::ExactOutputSingle(params) => params.deadline,
+            SwapParams::ExactInput(params) => params.deadline,
+        }
+    }
+
+    pub fn token_in(&self) -> Address {
+        self.hops()[0].token_in
+    }
+
+    // trade modelling a single pool swap, None for a multi-hop swap whose legs depend on the pools (see exact_input_legs)
+    pub fn single_trade(&self) -> Option<Trade> {
+        let hop = &self.hops()[0];
+        let (trade, limit) = match self {
+            SwapParams::ExactInputSingle(params) => (
+                Trade::new(params.amountIn, hop.direction()).with_amount_out_minimum(params.amountOutMinimum),
+                params.sqrtPriceLimitX96,
+            ),
+            SwapParams::ExactOutputSingle(params) => (
+                Trade::new_exact_output(params.amountOut, params.amountInMaximum, hop.direction()),
+                params.sqrtPriceLimitX96,
+            ),
+            SwapParams::ExactInput(_) => return None,
+        };
+
+        // the router takes a zero limit as no limit
+        let trade = trade.with_fee(hop.fee);
+        Some(if limit.is_zero() { trade } else { trade.with_sqrt_price_limit_x96(limit) })
+    }
+
+    // single pool swap executing trade in the pool of hop, e.g. a part of a routed swap
+    pub fn from_trade(hop: &Hop, trade: &dyn ITrade, recipient: Address, deadline: U256) -> Self {
+        // the router takes a zero limit as no limit
+        let sqrt_price_limit_x96 = trade.get_sqrt_price_limit_x96().unwrap_or(U160::ZERO);
+
+        match trade.get_amount_out() {
+            None => SwapParams::ExactInputSingle(ExactInputSingleParams {
+                tokenIn: hop.token_in,
+                tokenOut: hop.token_out,
+                fee: hop.fee,
+                recipient,
+                deadline,
+                amountIn: trade.get_amount_in(),
+                amountOutMinimum: trade.get_amount_out_minimum(),
+                sqrtPriceLimitX96: sqrt_price_limit_x96,
+            }),
+            Some(amount_out) => SwapParams::ExactOutputSingle(ExactOutputSingleParams {
+                tokenIn: hop.token_in,
+                tokenOut: hop.token_out,
+                fee: hop.fee,
+                recipient,
+                deadline,
+                amountOut: amount_out,
+                amountInMaximum: trade.get_amount_in(),
+                sqrtPriceLimitX96: sqrt_price_limit_x96,
+            }),
+        }
+    }
+
+    // router call executing the swap, a multi-hop swap executes all its legs in a single exactInput
+    pub fn calldata(&self) -> Bytes {
+        match self {
+            SwapParams::ExactInputSingle(params) => exactInputSingleCall { params: params.clone() }.abi_encode().into(),
+            SwapParams::ExactOutputSingle(params) => exactOutputSingleCall { params: params.clone() }.abi_encode().into(),
+            SwapParams::ExactInput(params) => exactInputCall { params: params.clone() }.abi_encode().into(),
+        }
+    }
+}
+
 impl From<ExactInputSingleParamsIntermediate> for ExactInputSingleParams {
     fn from(params: ExactInputSingleParamsIntermediate) -> Self {
         ExactInputSingleParams {
@@ -49,6 +239,43 @@ impl From<ExactInputSingleParamsIntermediate> for ExactInputSingleParams {
     }
 }
 
+impl From<ExactOutputSingleParamsIntermediate> for ExactOutputSingleParams {
+    fn from(params: ExactOutputSingleParamsIntermediate) -> Self {
+        ExactOutputSingleParams {
+            tokenIn: Address::from_str(&params.token_in).unwrap(), 
+            tokenOut: Address::from_str(&params.token_out).unwrap(), 
+            fee: params.fee, 
+            recipient: Address::from_str(&params.recipient).unwrap(), 
+            deadline: params.deadline,
+            amountOut: params.amount_out,
+            amountInMaximum: params.amount_in_maximum,
+            sqrtPriceLimitX96: params.sqrt_price_limit_x96,
+        }
+    }
+}
+
+impl From<ExactInputParamsIntermediate> for ExactInputParams {
+    fn from(params: ExactInputParamsIntermediate) -> Self {
+        ExactInputParams {
+            path: hex::decode(&params.path).unwrap().into(),
+            recipient: Address::from_str(&params.recipient).unwrap(),
+            deadline: params.deadline,
+            amountIn: params.amount_in,
+            amountOutMinimum: params.amount_out_minimum,
+        }
+    }
+}
+
+impl From<SwapParamsIntermediate> for SwapParams {
+    fn from(params: SwapParamsIntermediate) -> Self {
+        match params {
+            SwapParamsIntermediate::ExactInputSingle(params) => SwapParams::ExactInputSingle(params.into()),
+            SwapParamsIntermediate::ExactOutputSingle(params) => SwapParams::ExactOutputSingle(params.into()),
+            SwapParamsIntermediate::ExactInput(params) => SwapParams::ExactInput(params.into()),
+        }
+    }
+}
+
 use std::fmt::{Debug, Error, Formatter};
 impl Debug for ExactInputSingleParams {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
@@ -56,3 +283,17 @@ impl Debug for ExactInputSingleParams {
         self.tokenIn, self.tokenOut, self.fee, self.recipient, self.deadline, self.amountIn, self.amountOutMinimum, self.sqrtPriceLimitX96)
     }
 }
+
+impl Debug for ExactOutputSingleParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "ExactOutputSingleParams {{ tokenIn: {:?}, tokenOut: {:?}, fee: {:?}, recipient: {:?}, deadline: {:?}, amountOut: {:?}, amountInMaximum: {:?}, sqrtPriceLimitX96: {:?} }}", 
+        self.tokenIn, self.tokenOut, self.fee, self.recipient, self.deadline, self.amountOut, self.amountInMaximum, self.sqrtPriceLimitX96)
+    }
+}
+
+impl Debug for ExactInputParams {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "ExactInputParams {{ path: {:?}, recipient: {:?}, deadline: {:?}, amountIn: {:?}, amountOutMinimum: {:?} }}", 
+        self.path, self.recipient, self.deadline, self.amountIn, self.amountOutMinimum)
+    }
+}
@@ -0,0 +1,176 @@
+use alloy::primitives::{keccak256, Address, Bytes, B256, I256, U160, U256};
+use alloy::sol;
+use alloy::sol_types::{SolCall, SolValue};
+use uniswap_v3_sdk::prelude::{MAX_SQRT_RATIO, MIN_SQRT_RATIO};
+use crate::clvr::metrics::Quote;
+use crate::trades::{ITrade, TradeDirection};
+use IClvrSettlement::{executeCall, settleCall, Payout, Swap};
+
+sol! {
+    // Identifies a pool of the V4 PoolManager, currency0 is the lower address, the zero address being native ETH
+    #[derive(Debug, PartialEq, Eq)]
+    struct PoolKey {
+        address currency0;
+        address currency1;
+        uint24 fee;
+        int24 tickSpacing;
+        address hooks;
+    }
+
+    // Uniswap V4 SwapParams
+    struct SwapParams {
+        bool zeroForOne;
+        int256 amountSpecified;
+        uint160 sqrtPriceLimitX96;
+    }
+
+    // Executes an ordered batch on V4 pools (see contracts/ClvrSettlement.sol): execute unlocks the PoolManager, whose
+    // unlockCallback performs the swaps in order. Each swap reverts the batch unless it receives at least
+    // amountOutMinimum and pays at most amountInMaximum, what its from owes is pulled through its allowance and what it
+    // receives paid to its recipient
+    #[sol(rpc)]
+    interface IClvrSettlement {
+        struct Swap {
+            PoolKey key;
+            address from;
+            address recipient;
+            SwapParams params;
+            uint256 amountOutMinimum;
+            uint256 amountInMaximum;
+        }
+
+        // what a trader of a settled batch pays and receives, whatever part of it is matched within the batch
+        struct Payout {
+            address from;
+            address recipient;
+            bool zeroForOne;
+            uint256 amountIn;
+            uint256 amountOut;
+            uint256 amountOutMinimum;
+            uint256 amountInMaximum;
+        }
+
+        function execute(Swap[] calldata swaps) external;
+
+        // Settles a batch of a single pool at once: the pool executes swaps, then every payout is pulled from its
+        // from and paid to its recipient. Reverts unless every payout is within its bounds and the pool's swaps
+        // cover the payouts, what is left over is returned to the payouts which paid it
+        function settle(PoolKey calldata key, SwapParams[] calldata swaps, Payout[] calldata payouts) external;
+    }
+}
+
+// tick spacing of the pools created with a standard fee tier
+fn tick_spacing(fee: u32) -> i32 {
+    match fee {
+        100 => 1,
+        500 => 10,
+        3000 => 60,
+        _ => 200,
+    }
+}
+
+// key of the pool of two tokens with a standard fee tier and the given hooks
+pub fn pool_key(token_x: Address, token_y: Address, fee: u32, hooks: Address) -> PoolKey {
+    let (currency0, currency1) = if token_x < token_y { (token_x, token_y) } else { (token_y, token_x) };
+
+    PoolKey {
+        currency0,
+        currency1,
+        fee: fee.try_into().unwrap(),
+        tickSpacing: tick_spacing(fee).try_into().unwrap(),
+        hooks,
+    }
+}
+
+// PoolId of a pool, keccak256 of its abi encoded key
+pub fn pool_id(key: &PoolKey) -> B256 {
+    keccak256(key.abi_encode())
+}
+
+// V4 pools live in the PoolManager and have no address of their own, a pool is keyed by the last 20 bytes of its PoolId
+pub fn pool_address(key: &PoolKey) -> Address {
+    Address::from_slice(&pool_id(key)[12..])
+}
+
+// SwapParams of a trade, token x being currency0. A negative amountSpecified is an exact input, a positive one an exact
+// output. Without a limit the swap may move the price up to the bounds of the pool
+pub fn swap_params(trade: &dyn ITrade) -> SwapParams {
+    let zero_for_one = trade.get_direction() == TradeDirection::Sell;
+    let amount_specified = match trade.get_amount_out() {
+        None => -I256::try_from(trade.get_amount_in()).unwrap(),
+        Some(amount_out) => I256::try_from(amount_out).unwrap(),
+    };
+    let sqrt_price_limit_x96 = trade.get_sqrt_price_limit_x96().unwrap_or(if zero_for_one {
+        MIN_SQRT_RATIO + U160::from(1)
+    } else {
+        MAX_SQRT_RATIO - U160::from(1)
+    });
+
+    SwapParams {
+        zeroForOne: zero_for_one,
+        amountSpecified: amount_specified,
+        sqrtPriceLimitX96: sqrt_price_limit_x96,
+    }
+}
+
+// bounds (amountOutMinimum, amountInMaximum) of the swap of a trade: an exact input pays its amount in and must receive
+// its minimum, an exact output may pay up to its maximum and, with a price limit, be partially filled
+pub fn bounds(trade: &dyn ITrade) -> (U256, U256) {
+    match trade.get_amount_out() {
+        None => (trade.get_amount_out_minimum(), trade.get_amount_in()),
+        Some(_) if trade.get_sqrt_price_limit_x96().is_some() => (U256::ZERO, trade.get_amount_in()),
+        Some(amount_out) => (amount_out, trade.get_amount_in()),
+    }
+}
+
+// settlement call executing the trades in order, each on the pool of its key, paid by from and delivered to recipient
+pub fn execute_calldata(trades: &[(&PoolKey, Address, Address, &dyn ITrade)]) -> Bytes {
+    let swaps = trades
+        .iter()
+        .map(|&(key, from, recipient, trade)| {
+            let (amount_out_minimum, amount_in_maximum) = bounds(trade);
+            Swap {
+                key: key.clone(),
+                from,
+                recipient,
+                params: swap_params(trade),
+                amountOutMinimum: amount_out_minimum,
+                amountInMaximum: amount_in_maximum,
+            }
+        })
+        .collect();
+
+    executeCall { swaps }.abi_encode().into()
+}
+
+// settlement call executing swaps in the pool of key and paying every trade of the batch what it is quoted, each paid
+// by from and delivered to recipient. An exact input trade paying part of its amount in must receive the same share
+// of its minimum out
+pub fn settle_calldata(key: &PoolKey, swaps: &[&dyn ITrade], payouts: &[(Address, Address, &dyn ITrade, Quote)]) -> Bytes {
+    let payouts = payouts
+        .iter()
+        .map(|(from, recipient, trade, quote)| {
+            let (amount_out_minimum, amount_in_maximum) = match trade.get_amount_out() {
+                None => (trade.get_amount_out_minimum() * quote.amount_in / trade.get_amount_in(), trade.get_amount_in()),
+                Some(_) => bounds(*trade),
+            };
+            Payout {
+                from: *from,
+                recipient: *recipient,
+                zeroForOne: trade.get_direction() == TradeDirection::Sell,
+                amountIn: quote.amount_in,
+                amountOut: quote.amount_out,
+                amountOutMinimum: amount_out_minimum,
+                amountInMaximum: amount_in_maximum,
+            }
+        })
+        .collect();
+
+    settleCall {
+        key: key.clone(),
+        swaps: swaps.iter().map(|&trade| swap_params(trade)).collect(),
+        payouts,
+    }
+    .abi_encode()
+    .into()
+}
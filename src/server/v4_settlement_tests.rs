@@ -0,0 +1,94 @@
+use crate::clvr::metrics::Quote;
+use crate::server::v4_settlement::{bounds, execute_calldata, pool_address, pool_id, pool_key, settle_calldata, swap_params, IClvrSettlement::{executeCall, settleCall}, PoolKey};
+use crate::trades::implementation::Trade;
+use crate::trades::{ITrade, TradeDirection};
+use alloy::primitives::{address, aliases::U160, Address, I256, U256};
+use alloy::sol_types::SolCall;
+use uniswap_v3_sdk::prelude::{MAX_SQRT_RATIO, MIN_SQRT_RATIO};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_key() {
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+
+        // native ETH is currency0, a 0.05% pool has a tick spacing of 10
+        let key = pool_key(usdc, Address::ZERO, 500, Address::ZERO);
+        assert_eq!((key.currency0, key.currency1), (Address::ZERO, usdc));
+        assert_eq!(key.tickSpacing.as_i32(), 10);
+        assert_eq!(pool_key(Address::ZERO, usdc, 500, Address::ZERO), key);
+
+        assert_eq!(pool_address(&key).as_slice(), &pool_id(&key)[12..]);
+        assert_ne!(pool_address(&key), pool_address(&pool_key(usdc, Address::ZERO, 3000, Address::ZERO)));
+    }
+
+    #[test]
+    fn test_swap_params() {
+        // an exact input is negative, without a limit the price may move to the bound of the pool
+        let sell = Trade::new(U256::from(10), TradeDirection::Sell);
+        let params = swap_params(&sell);
+        assert!(params.zeroForOne);
+        assert_eq!(params.amountSpecified, I256::try_from(-10).unwrap());
+        assert_eq!(params.sqrtPriceLimitX96, MIN_SQRT_RATIO + U160::from(1));
+
+        let buy = Trade::new_exact_output(U256::from(10), U256::from(20), TradeDirection::Buy);
+        let params = swap_params(&buy);
+        assert!(!params.zeroForOne);
+        assert_eq!(params.amountSpecified, I256::try_from(10).unwrap());
+        assert_eq!(params.sqrtPriceLimitX96, MAX_SQRT_RATIO - U160::from(1));
+
+        let limited = Trade::new(U256::from(10), TradeDirection::Sell).with_sqrt_price_limit_x96(U160::from(1) << 96);
+        assert_eq!(swap_params(&limited).sqrtPriceLimitX96, U160::from(1) << 96);
+    }
+
+    #[test]
+    fn test_execute_calldata() {
+        let key = pool_key(Address::ZERO, Address::with_last_byte(1), 3000, Address::ZERO);
+        let other = pool_key(Address::ZERO, Address::with_last_byte(1), 500, Address::ZERO);
+        let (from, recipient) = (Address::with_last_byte(2), Address::with_last_byte(3));
+        let sell = Trade::new(U256::from(10), TradeDirection::Sell).with_amount_out_minimum(U256::from(4));
+        let buy = Trade::new_exact_output(U256::from(5), U256::from(12), TradeDirection::Buy);
+        let trades: Vec<(&PoolKey, Address, Address, &dyn ITrade)> = vec![(&key, from, recipient, &buy), (&other, recipient, from, &sell)];
+
+        // the swaps are encoded in execution order, each with its pool and bounds
+        let call = executeCall::abi_decode(&execute_calldata(&trades), true).unwrap();
+        assert_eq!(call.swaps.len(), 2);
+        assert_eq!((call.swaps[0].key.clone(), call.swaps[1].key.clone()), (key, other));
+        assert_eq!((call.swaps[0].from, call.swaps[0].recipient), (from, recipient));
+        assert!(!call.swaps[0].params.zeroForOne);
+        assert_eq!((call.swaps[0].amountOutMinimum, call.swaps[0].amountInMaximum), (U256::from(5), U256::from(12)));
+        assert!(call.swaps[1].params.zeroForOne);
+        assert_eq!((call.swaps[1].amountOutMinimum, call.swaps[1].amountInMaximum), (U256::from(4), U256::from(10)));
+
+        // a limited exact output may be partially filled
+        let limited = Trade::new_exact_output(U256::from(5), U256::from(12), TradeDirection::Buy).with_sqrt_price_limit_x96(U160::from(1) << 96);
+        assert_eq!(bounds(&limited), (U256::ZERO, U256::from(12)));
+    }
+
+    #[test]
+    fn test_settle_calldata() {
+        let key = pool_key(Address::ZERO, Address::with_last_byte(1), 3000, Address::ZERO);
+        let (from, recipient) = (Address::with_last_byte(2), Address::with_last_byte(3));
+        let sell = Trade::new(U256::from(10), TradeDirection::Sell).with_amount_out_minimum(U256::from(4));
+        let buy = Trade::new_exact_output(U256::from(5), U256::from(12), TradeDirection::Buy);
+        let residual = Trade::new(U256::from(6), TradeDirection::Sell);
+        let quote = |amount_in: u64, amount_out: u64| Quote { amount_in: U256::from(amount_in), amount_out: U256::from(amount_out), partial: false };
+        let payouts: Vec<(Address, Address, &dyn ITrade, Quote)> = vec![(from, recipient, &sell, quote(5, 3)), (recipient, from, &buy, quote(11, 5))];
+
+        // the pool executes the residual, every trade is paid its quote within its bounds
+        let call = settleCall::abi_decode(&settle_calldata(&key, &[&residual], &payouts), true).unwrap();
+        assert_eq!(call.key, key);
+        assert_eq!(call.swaps.len(), 1);
+        assert_eq!(call.swaps[0].amountSpecified, I256::try_from(-6).unwrap());
+        assert_eq!(call.payouts.len(), 2);
+        assert_eq!((call.payouts[0].from, call.payouts[0].recipient), (from, recipient));
+        assert!(call.payouts[0].zeroForOne);
+        assert_eq!((call.payouts[0].amountIn, call.payouts[0].amountOut), (U256::from(5), U256::from(3)));
+        // half the amount in must receive half the minimum
+        assert_eq!((call.payouts[0].amountOutMinimum, call.payouts[0].amountInMaximum), (U256::from(2), U256::from(10)));
+        assert!(!call.payouts[1].zeroForOne);
+        assert_eq!((call.payouts[1].amountOutMinimum, call.payouts[1].amountInMaximum), (U256::from(5), U256::from(12)));
+    }
+}
@@ -1,20 +1,63 @@
 use crate::trades::{ITrade, TradeDirection};
-use alloy::primitives::U256;
-use serde::{Deserialize, Serialize};
+use alloy::primitives::{aliases::U24, U160, U256};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(PartialEq, Debug, Deserialize, Serialize)]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct Trade {
     amount_in: U256,
+    amount_out: Option<U256>,
+    amount_out_minimum: U256,
+    sqrt_price_limit_x96: Option<U160>,
     direction: TradeDirection,
+    fee: U24,
+    #[serde(default)]
+    pool: usize,
 }
 
 impl Trade {
     pub fn new(amount_in: U256, direction: TradeDirection) -> Self {
         Trade {
             amount_in,
+            amount_out: None,
+            amount_out_minimum: U256::ZERO,
+            sqrt_price_limit_x96: None,
             direction,
+            fee: U24::ZERO,
+            pool: 0,
         }
     }
+
+    pub fn new_exact_output(amount_out: U256, amount_in_maximum: U256, direction: TradeDirection) -> Self {
+        Trade {
+            amount_in: amount_in_maximum,
+            amount_out: Some(amount_out),
+            amount_out_minimum: U256::ZERO,
+            sqrt_price_limit_x96: None,
+            direction,
+            fee: U24::ZERO,
+            pool: 0,
+        }
+    }
+
+    pub fn with_fee(mut self, fee: U24) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn with_pool(mut self, pool: usize) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    pub fn with_amount_out_minimum(mut self, amount_out_minimum: U256) -> Self {
+        self.amount_out_minimum = amount_out_minimum;
+        self
+    }
+
+    pub fn with_sqrt_price_limit_x96(mut self, sqrt_price_limit_x96: U160) -> Self {
+        self.sqrt_price_limit_x96 = Some(sqrt_price_limit_x96);
+        self
+    }
 }
 
 impl ITrade for Trade {
@@ -25,4 +68,51 @@ impl ITrade for Trade {
     fn get_amount_in(&self) -> U256 {
         self.amount_in
     }
+
+    fn get_amount_out(&self) -> Option<U256> {
+        self.amount_out
+    }
+
+    fn get_amount_out_minimum(&self) -> U256 {
+        self.amount_out_minimum
+    }
+
+    fn get_sqrt_price_limit_x96(&self) -> Option<U160> {
+        self.sqrt_price_limit_x96
+    }
+
+    fn get_fee(&self) -> U24 {
+        self.fee
+    }
+
+    fn get_pool(&self) -> usize {
+        self.pool
+    }
+}
+
+impl From<&dyn ITrade> for Trade {
+    fn from(trade: &dyn ITrade) -> Self {
+        Trade {
+            amount_in: trade.get_amount_in(),
+            amount_out: trade.get_amount_out(),
+            amount_out_minimum: trade.get_amount_out_minimum(),
+            sqrt_price_limit_x96: trade.get_sqrt_price_limit_x96(),
+            direction: trade.get_direction(),
+            fee: trade.get_fee(),
+            pool: trade.get_pool(),
+        }
+    }
+}
+
+// trades of any type are serialized as the equivalent Trade, and boxed trades deserialized as one
+impl Serialize for dyn ITrade {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Trade::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn ITrade> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Box::new(Trade::deserialize(deserializer)?))
+    }
 }
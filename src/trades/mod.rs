@@ -1,7 +1,11 @@
-use alloy::primitives::U256;
+use alloy::primitives::{aliases::U24, U160, U256};
 use serde::{Serialize, Deserialize};
 
 pub mod implementation;
+pub mod path;
+
+#[cfg(test)]
+mod path_tests;
 
 #[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum TradeDirection {
@@ -9,7 +13,43 @@ pub enum TradeDirection {
     Sell,
 }
 
-pub trait ITrade {
+pub trait ITrade: Send + Sync {
     fn get_direction(&self) -> TradeDirection;
     fn get_amount_in(&self) -> U256; // INVARIANT: when direction == Buy, amount_in is in tokens y, when direction == Sell, amount_in is in tokens x
+    fn get_amount_out(&self) -> Option<U256>; // Some for exact output trades, amount_in is then the maximum the trader pays
+    fn get_amount_out_minimum(&self) -> U256; // the swap reverts if an exact input trade receives less
+    fn get_sqrt_price_limit_x96(&self) -> Option<U160>; // the swap stops, partially filled, once the pool's sqrt price reaches the limit
+    fn get_fee(&self) -> U24; // fee tier of the pool the trade is executed in, in hundredths of a bip (3000 = 0.3%)
+    fn get_pool(&self) -> usize; // index of the pool the trade is executed in among linked pools ordered jointly, 0 otherwise
+}
+
+// a boxed trade is a trade, so that orderings of boxed trades of different types are orderings too
+impl<T: ITrade + ?Sized> ITrade for Box<T> {
+    fn get_direction(&self) -> TradeDirection {
+        self.as_ref().get_direction()
+    }
+
+    fn get_amount_in(&self) -> U256 {
+        self.as_ref().get_amount_in()
+    }
+
+    fn get_amount_out(&self) -> Option<U256> {
+        self.as_ref().get_amount_out()
+    }
+
+    fn get_amount_out_minimum(&self) -> U256 {
+        self.as_ref().get_amount_out_minimum()
+    }
+
+    fn get_sqrt_price_limit_x96(&self) -> Option<U160> {
+        self.as_ref().get_sqrt_price_limit_x96()
+    }
+
+    fn get_fee(&self) -> U24 {
+        self.as_ref().get_fee()
+    }
+
+    fn get_pool(&self) -> usize {
+        self.as_ref().get_pool()
+    }
 }
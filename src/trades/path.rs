@@ -0,0 +1,49 @@
+use crate::trades::TradeDirection;
+use alloy::primitives::{aliases::U24, Address};
+
+const ADDRESS_LENGTH: usize = 20;
+const FEE_LENGTH: usize = 3;
+
+// A pool of a swap path: token_in is swapped for token_out in the pool of the two tokens with the given fee tier
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hop {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub fee: U24,
+}
+
+impl Hop {
+    // direction of the hop in its pool, whose token x (token0) is the lower address
+    pub fn direction(&self) -> TradeDirection {
+        if self.token_in < self.token_out {
+            TradeDirection::Sell
+        } else {
+            TradeDirection::Buy
+        }
+    }
+}
+
+// Decodes a router path token, fee, token, fee, ..., token (20 + 3 + 20 + ... bytes), None if it is malformed
+pub fn decode_path(path: &[u8]) -> Option<Vec<Hop>> {
+    let hop_length = FEE_LENGTH + ADDRESS_LENGTH;
+    if path.len() < ADDRESS_LENGTH + hop_length || !(path.len() - ADDRESS_LENGTH).is_multiple_of(hop_length) {
+        return None;
+    }
+
+    let mut token_in = Address::from_slice(&path[..ADDRESS_LENGTH]);
+    let hops = path[ADDRESS_LENGTH..]
+        .chunks(hop_length)
+        .map(|hop| {
+            let token_out = Address::from_slice(&hop[FEE_LENGTH..]);
+            let hop = Hop {
+                token_in,
+                token_out,
+                fee: U24::from_be_slice(&hop[..FEE_LENGTH]),
+            };
+            token_in = token_out;
+            hop
+        })
+        .collect();
+
+    Some(hops)
+}
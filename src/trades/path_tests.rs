@@ -0,0 +1,41 @@
+use crate::trades::path::{decode_path, Hop};
+use crate::trades::TradeDirection;
+use alloy::primitives::{address, aliases::U24, hex};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        let usdc = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+        let weth = address!("c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2");
+        let usdt = address!("dac17f958d2ee523a2206206994597c13d831ec7");
+
+        // USDC -> WETH at 0.05%, WETH -> USDT at 0.3%
+        let path = hex::decode(
+            "a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48\
+             0001f4\
+             c02aaa39b223fe8d0a0e5c4f27ead9083c756cc2\
+             000bb8\
+             dac17f958d2ee523a2206206994597c13d831ec7",
+        )
+        .unwrap();
+        let hops = decode_path(&path).unwrap();
+        assert_eq!(
+            hops,
+            vec![
+                Hop { token_in: usdc, token_out: weth, fee: U24::from(500) },
+                Hop { token_in: weth, token_out: usdt, fee: U24::from(3000) },
+            ]
+        );
+
+        // token0 is the lower address, USDC is token0 of USDC/WETH and USDT token1 of WETH/USDT
+        assert_eq!(hops[0].direction(), TradeDirection::Sell);
+        assert_eq!(hops[1].direction(), TradeDirection::Sell);
+
+        // a path needs two tokens and whole hops
+        assert!(decode_path(&path[..20]).is_none());
+        assert!(decode_path(&path[..path.len() - 1]).is_none());
+    }
+}